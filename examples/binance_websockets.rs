@@ -207,8 +207,6 @@ async fn mark_price_websocket(logger_tx: UnboundedSender<WebsocketEvent>) {
         Ok(())
     });
 
-    
-    
     if let Err(e) = web_socket.connect_futures(&mark_price).await {
         println!("Connection error: {e}");
     }
@@ -324,7 +322,7 @@ async fn custom_event_loop(logger_tx: UnboundedSender<WebsocketEvent>) {
         });
     web_socket.connect_multiple(streams).await.unwrap(); // check error
     loop {
-        if let Some((ref mut socket, _)) = web_socket.socket {
+        if let Some((ref mut socket, _)) = web_socket.socket_handler.socket {
             if let Ok(message) = socket.next().await.unwrap() {
                 match message {
                     Message::Text(msg) => {
@@ -46,7 +46,7 @@ pub struct OrderRequest {
 }
 
 impl OrderRequest {
-    fn valid(&self) -> Result<()> {
+    pub(crate) fn valid(&self) -> Result<()> {
         if self.iceberg_qty.is_some() && self.time_in_force != Some(TimeInForce::GTC) {
             return Err(Error::InvalidOrderError {
                 msg: "Time in force has to be GTC for iceberg orders".to_string(),
@@ -1,8 +1,10 @@
 use crate::account::*;
 use crate::client::*;
 use crate::config::Config;
+use crate::credentials::{CredentialProvider, Credentials};
 use crate::general::*;
 use crate::market::*;
+use crate::signer::KeyType;
 use crate::userstream::*;
 
 pub trait Binance: Sized {
@@ -19,13 +21,65 @@ pub trait Binance: Sized {
         Self::new_with_config(api_key, secret, config)
     }
 
+    /// Create a binance API signing requests with an Ed25519 or RSA private key in PEM format
+    fn new_with_private_key(api_key: Option<String>, private_key: String, key_type: KeyType, config: &Config) -> Self {
+        Self::new_with_config(api_key, Some(private_key), &config.clone().set_key_type(key_type))
+    }
+
+    /// Create a binance API asking `provider` for credentials on every request needing them
+    fn new_with_credentials(provider: impl CredentialProvider + 'static, config: &Config) -> Self {
+        Self::new_with_config(None, None, &config.clone().set_credential_provider(provider))
+    }
+
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self;
 }
 
+/// Creates API structs from one config and one set of credentials, so that they all share the
+/// config's connection pool, rate limiter, time sync and credential provider
+///
+/// # Examples
+///
+/// ```
+/// use binance::{account::*, api::*, config::*, market::*};
+/// let factory = BinanceClientFactory::new(None, None, Config::default());
+/// let market: Market = factory.create();
+/// let account: Account = factory.create();
+/// ```
+#[derive(Clone, Debug)]
+pub struct BinanceClientFactory {
+    config: Config,
+}
+
+impl BinanceClientFactory {
+    /// A factory using the given keys, or the credential provider of the config when no key is given
+    pub fn new(api_key: Option<String>, secret_key: Option<String>, config: Config) -> Self {
+        let config = if api_key.is_some() || secret_key.is_some() {
+            config.set_credential_provider(Credentials::new(
+                api_key.unwrap_or_default(),
+                secret_key.unwrap_or_default(),
+            ))
+        } else {
+            config
+        };
+        Self { config }
+    }
+
+    /// A factory using the `BINANCE_API_KEY` and `BINANCE_API_SECRET_KEY` environment variables for credentials
+    pub fn new_with_env(config: Config) -> Self {
+        let api_key = std::env::var("BINANCE_API_KEY").ok();
+        let secret = std::env::var("BINANCE_API_SECRET_KEY").ok();
+        Self::new(api_key, secret, config)
+    }
+
+    pub fn config(&self) -> &Config { &self.config }
+
+    pub fn create<T: Binance>(&self) -> T { T::new_with_config(None, None, &self.config) }
+}
+
 impl Binance for General {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> General {
         General {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
         }
     }
 }
@@ -33,7 +87,7 @@ impl Binance for General {
 impl Binance for Account {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Account {
         Account {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -43,7 +97,7 @@ impl Binance for Account {
 impl Binance for crate::savings::Savings {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -52,7 +106,7 @@ impl Binance for crate::savings::Savings {
 impl Binance for Market {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Market {
         Market {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -61,7 +115,7 @@ impl Binance for Market {
 impl Binance for UserStream {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> UserStream {
         UserStream {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -71,12 +125,7 @@ impl Binance for UserStream {
 impl Binance for crate::futures::general::FuturesGeneral {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.futures_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.futures_rest_api_endpoint.clone(), config),
         }
     }
 }
@@ -85,12 +134,7 @@ impl Binance for crate::futures::general::FuturesGeneral {
 impl Binance for crate::futures::market::FuturesMarket {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.futures_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.futures_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -100,12 +144,7 @@ impl Binance for crate::futures::market::FuturesMarket {
 impl Binance for crate::futures::account::FuturesAccount {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.futures_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.futures_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -115,12 +154,7 @@ impl Binance for crate::futures::account::FuturesAccount {
 impl Binance for crate::futures::userstream::UserStream {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.futures_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.futures_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -130,7 +164,7 @@ impl Binance for crate::futures::userstream::UserStream {
 impl Binance for crate::margin::Margin {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -140,7 +174,7 @@ impl Binance for crate::margin::Margin {
 impl Binance for crate::wallet::Wallet {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(api_key, secret_key, config.rest_api_endpoint.clone(), config.timeout),
+            client: Client::new_with_config(api_key, secret_key, config.rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
             binance_us_api: config.binance_us_api,
         }
@@ -150,12 +184,7 @@ impl Binance for crate::wallet::Wallet {
 impl Binance for crate::coin_margin::market::CoinMarket {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.coinm_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.coinm_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -164,12 +193,7 @@ impl Binance for crate::coin_margin::market::CoinMarket {
 impl Binance for crate::coin_margin::account::CoinAccount {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.coinm_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.coinm_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -178,12 +202,7 @@ impl Binance for crate::coin_margin::account::CoinAccount {
 impl Binance for crate::coin_margin::general::CoinGeneral {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.coinm_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.coinm_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -192,12 +211,7 @@ impl Binance for crate::coin_margin::general::CoinGeneral {
 impl Binance for crate::coin_margin::userstream::UserStream {
     fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         Self {
-            client: Client::new(
-                api_key,
-                secret_key,
-                config.coinm_rest_api_endpoint.clone(),
-                config.timeout,
-            ),
+            client: Client::new_with_config(api_key, secret_key, config.coinm_rest_api_endpoint.clone(), config),
             recv_window: config.recv_window,
         }
     }
@@ -0,0 +1,316 @@
+//! Backfill of market and account history over any time range.
+//!
+//! History endpoints return at most 500 to 1500 rows per request. The backfill streams, such as
+//! [`crate::market::Market::backfill_klines`], send as many requests as needed, each starting past
+//! the last row received, skip the rows received twice at page boundaries and end with the range.
+//! The account history helpers, such as
+//! [`crate::futures::account::FuturesAccount::user_trades_quick`], query time windows instead,
+//! split in halves while their page is full, since a full page may hold the most recent rows of the
+//! window rather than the oldest ones.
+//!
+//! Requests go through the rate limiter of the client, which charges each page the documented
+//! weight of its endpoint and holds it back while the weight used is at the limits of the
+//! exchange, see [`crate::config::Config::set_rate_limiter`]. Unless `exchange_info` was already
+//! called on the same config, the limits are seeded from the exchange information before the first
+//! page.
+
+use std::future::Future;
+
+use futures::stream::{self, Stream, TryStreamExt};
+use serde::Deserialize;
+
+use crate::client::Client;
+use crate::errors::*;
+use crate::rate_limit::RateLimitPolicy;
+use crate::rest_model::RateLimit;
+
+/// Rows per request of spot klines
+pub static SPOT_KLINES_PAGE_SIZE: u16 = 1000;
+/// Rows per request of spot aggregated trades
+pub static SPOT_AGG_TRADES_PAGE_SIZE: u16 = 1000;
+/// Rows per request of futures klines
+pub static FUTURES_KLINES_PAGE_SIZE: u16 = 1500;
+/// Rows per request of futures aggregated trades
+pub static FUTURES_AGG_TRADES_PAGE_SIZE: u16 = 1000;
+/// Rows per request of futures historical trades
+pub static FUTURES_HISTORICAL_TRADES_PAGE_SIZE: u16 = 500;
+/// Rows per request of futures orders of the account
+pub static FUTURES_ALL_ORDERS_PAGE_SIZE: u16 = 1000;
+/// Rows per request of futures trades of the account
+pub static FUTURES_USER_TRADES_PAGE_SIZE: u16 = 1000;
+/// Rows per request of futures incomes of the account
+pub static FUTURES_INCOME_PAGE_SIZE: u16 = 1000;
+
+/// Exchange information of spot, the symbol of the backfill keeps the response small
+pub(crate) static SPOT_EXCHANGE_INFO: &str = "/api/v3/exchangeInfo";
+/// Exchange information of USD-M futures
+pub(crate) static FUTURES_EXCHANGE_INFO: &str = "/fapi/v1/exchangeInfo";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExchangeRateLimits {
+    rate_limits: Vec<RateLimit>,
+}
+
+/// Seeds the rate limits of the host of `client` from the exchange information at `endpoint`,
+/// unless they are already known or only tracked
+pub(crate) async fn seed_rate_limits(client: &Client, endpoint: &str, request: Option<&str>) -> Result<()> {
+    if client.rate_limiter().policy() == RateLimitPolicy::TrackOnly || client.has_rate_limits() {
+        return Ok(());
+    }
+    let info: ExchangeRateLimits = client.get(endpoint, request).await?;
+    client.set_rate_limits(&info.rate_limits);
+    Ok(())
+}
+
+/// A row of a history, ordered by id
+pub(crate) trait HistoryRow {
+    /// Increases from a row to the next, the open time of klines or the id of trades
+    fn id(&self) -> u64;
+
+    fn time(&self) -> u64;
+}
+
+impl HistoryRow for crate::rest_model::KlineSummary {
+    fn id(&self) -> u64 { self.open_time as u64 }
+
+    fn time(&self) -> u64 { self.open_time as u64 }
+}
+
+impl HistoryRow for crate::rest_model::AggTrade {
+    fn id(&self) -> u64 { self.agg_id }
+
+    fn time(&self) -> u64 { self.time }
+}
+
+#[cfg(feature = "futures_api")]
+impl HistoryRow for crate::futures::rest_model::AggTrade {
+    fn id(&self) -> u64 { self.agg_id }
+
+    fn time(&self) -> u64 { self.time }
+}
+
+#[cfg(feature = "futures_api")]
+impl HistoryRow for crate::futures::rest_model::Trade {
+    fn id(&self) -> u64 { self.id }
+
+    fn time(&self) -> u64 { self.time }
+}
+
+#[cfg(feature = "futures_api")]
+impl HistoryRow for crate::futures::rest_model::UserTrade {
+    fn id(&self) -> u64 { self.id }
+
+    fn time(&self) -> u64 { self.time }
+}
+
+#[cfg(feature = "futures_api")]
+impl HistoryRow for crate::futures::rest_model::Order {
+    fn id(&self) -> u64 { self.order_id }
+
+    fn time(&self) -> u64 { self.time }
+}
+
+/// Where the page starts: the first page by time, the next ones by id since the exchange limits
+/// the time ranges of these histories, to an hour for aggregated trades
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Cursor {
+    StartTime(u64),
+    FromId(u64),
+}
+
+struct Pages<C, F> {
+    cursor: C,
+    last_id: Option<u64>,
+    fetch: F,
+}
+
+/// The rows of the pages returned by `fetch` from the `start` cursor, the cursor of the next page
+/// being `next` of the last row. Ends after the first row past `end_time`, a page shorter than
+/// `page_size` or a page of rows already received.
+pub(crate) fn paginate<T, C, F, Fut>(
+    start: C,
+    next: fn(&T) -> C,
+    end_time: Option<u64>,
+    page_size: u16,
+    fetch: F,
+) -> impl Stream<Item = Result<T>>
+where
+    T: HistoryRow,
+    C: Copy,
+    F: FnMut(C) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let pages = Pages {
+        cursor: start,
+        last_id: None,
+        fetch,
+    };
+    stream::try_unfold(Some(pages), move |pages| async move {
+        let Some(mut pages) = pages else {
+            return Ok::<_, Error>(None);
+        };
+        let page = (pages.fetch)(pages.cursor).await?;
+        let mut done = page.len() < usize::from(page_size);
+        let mut rows = Vec::with_capacity(page.len());
+        for row in page {
+            if end_time.is_some_and(|end_time| row.time() > end_time) {
+                done = true;
+                break;
+            }
+            if pages.last_id.is_some_and(|last_id| row.id() <= last_id) {
+                continue;
+            }
+            pages.last_id = Some(row.id());
+            pages.cursor = next(&row);
+            rows.push(row);
+        }
+        done |= rows.is_empty();
+        let rows = stream::iter(rows.into_iter().map(Ok::<T, Error>));
+        Ok(Some((rows, (!done).then_some(pages))))
+    })
+    .try_flatten()
+}
+
+/// The rows of `fetch` within the time window from `start_time` to `end_time`, oldest first. The
+/// window is split in halves until the page of each part is not full, a full page of a single
+/// millisecond being kept as it is.
+pub(crate) async fn split_window<T, F, Fut>(
+    start_time: u64,
+    end_time: u64,
+    page_size: u16,
+    mut fetch: F,
+) -> Result<Vec<T>>
+where
+    T: HistoryRow,
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let mut rows = vec![];
+    // The oldest part of the window is last
+    let mut windows = vec![(start_time, end_time)];
+    while let Some((start_time, end_time)) = windows.pop() {
+        let mut page = fetch(start_time, end_time).await?;
+        if page.len() >= usize::from(page_size) && start_time < end_time {
+            let middle = start_time + (end_time - start_time) / 2;
+            windows.push((middle + 1, end_time));
+            windows.push((start_time, middle));
+            continue;
+        }
+        page.sort_by_key(T::id);
+        rows.extend(page);
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[derive(Debug, PartialEq)]
+    struct Row(u64);
+
+    impl HistoryRow for Row {
+        fn id(&self) -> u64 { self.0 }
+
+        fn time(&self) -> u64 { self.0 * 10 }
+    }
+
+    /// Pages of 100 rows among 250, repeating the last row of the previous page
+    fn fetch(from_id: u64) -> impl Future<Output = Result<Vec<Row>>> {
+        let rows = (from_id.saturating_sub(1)..250).take(100).map(Row).collect();
+        async move { Ok(rows) }
+    }
+
+    #[tokio::test]
+    async fn test_paginates_without_duplicates() {
+        let rows: Vec<Row> = paginate(0, |row: &Row| row.0 + 1, None, 100, fetch)
+            .map(|row| row.unwrap())
+            .collect()
+            .await;
+        assert_eq!(rows, (0..250).map(Row).collect::<Vec<_>>());
+
+        let rows: Vec<Row> = paginate(20, |row: &Row| row.0 + 1, Some(1495), 100, fetch)
+            .map(|row| row.unwrap())
+            .collect()
+            .await;
+        assert_eq!(rows, (19..150).map(Row).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_splits_full_windows() {
+        // The most recent 100 rows among 250 within the window, newest first
+        let fetch = |start_time: u64, end_time: u64| async move {
+            let rows: Vec<Row> = (0..250)
+                .rev()
+                .map(Row)
+                .filter(|row| (start_time..=end_time).contains(&row.time()))
+                .take(100)
+                .collect();
+            Ok(rows)
+        };
+        let rows = split_window(0, 5000, 100, fetch).await.unwrap();
+        assert_eq!(rows, (0..250).map(Row).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_ends_with_an_error() {
+        let rows = paginate(
+            0,
+            |row: &Row| row.0 + 1,
+            None,
+            100,
+            |from_id| async move {
+                match from_id {
+                    0 => Ok((0..100).map(Row).collect()),
+                    _ => Err(Error::Msg("unavailable".to_string())),
+                }
+            },
+        );
+        let rows: Vec<Result<Row>> = rows.collect().await;
+        assert_eq!(rows.len(), 101);
+        assert!(rows[100].is_err());
+    }
+
+    fn agg_trades(ids: std::ops::Range<u64>) -> String {
+        let trades: Vec<_> = ids
+            .map(|id| {
+                serde_json::json!({
+                    "a": id, "p": "0.01633102", "q": "4.70443515", "f": id, "l": id,
+                    "T": 1_700_000_000_000u64 + id, "m": true, "M": true
+                })
+            })
+            .collect();
+        serde_json::to_string(&trades).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_backfills_agg_trades_by_id() {
+        use crate::api::Binance;
+        use crate::market::Market;
+        use crate::test_util::serve_bodies;
+
+        let rate_limits =
+            r#"{"rateLimits":[{"rateLimitType":"REQUEST_WEIGHT","interval":"MINUTE","intervalNum":1,"limit":6000}]}"#;
+        let (url, server) = serve_bodies([rate_limits.to_string(), agg_trades(0..1000), agg_trades(999..1010)]).await;
+        let conf = crate::config::Config::default().set_rest_api_endpoint(url.clone());
+        let market: Market = Binance::new_with_config(None, None, &conf);
+        let trades: Vec<_> = market
+            .backfill_agg_trades("BNBBTC", 1_700_000_000_000, None)
+            .map(|trade| trade.unwrap().agg_id)
+            .collect()
+            .await;
+        assert_eq!(trades, (0..1010).collect::<Vec<_>>());
+        let request_lines = server.await.unwrap();
+        assert_eq!(request_lines[0], "GET /api/v3/exchangeInfo?symbol=BNBBTC HTTP/1.1");
+        assert!(conf.rate_limiter.has_limits(&url));
+        assert!(
+            request_lines[1].contains("startTime=1700000000000"),
+            "{}",
+            request_lines[1]
+        );
+        assert!(request_lines[2].contains("fromId=1000"), "{}", request_lines[2]);
+        assert!(!request_lines[2].contains("startTime"), "{}", request_lines[2]);
+    }
+}
@@ -1,26 +1,38 @@
 use std::env;
-use std::time::Duration;
+use std::future::Future;
+use std::time::{Duration, Instant};
 
 use boolinator::Boolinator;
 use eyre::Context;
-use hex::encode as hex_encode;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, USER_AGENT};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, RETRY_AFTER, USER_AGENT};
+use reqwest::Method;
 use reqwest::StatusCode;
-use reqwest::{Method, Response};
-use ring::hmac;
 use serde::de::DeserializeOwned;
 use serde::{de, Serialize};
 
+use crate::config::Config;
+use crate::credentials::{CredentialProvider, Credentials, SharedCredentials};
 use crate::errors::error_messages;
 use crate::errors::*;
-use crate::util::{build_request_p, build_signed_request_p};
+use crate::http_pool::HttpPool;
+use crate::middleware::{HttpRequest, HttpResponse, Middleware, MiddlewareChain};
+use crate::rate_limit::{RateLimitUsage, RateLimiter, RequestCost, RetryPolicy};
+use crate::rest_model::RateLimit;
+use crate::signer::SignerCache;
+use crate::time_sync::TimeSync;
+use crate::util::{build_request_p, build_signed_request_p, refresh_timestamp};
 
 #[derive(Clone)]
 pub struct Client {
-    api_key: String,
-    secret_key: String,
-    inner: reqwest::Client,
+    credentials: SharedCredentials,
+    signer: SignerCache,
+    inner: HttpPool,
+    timeout: Option<Duration>,
     host: String,
+    rate_limiter: RateLimiter,
+    retry_policy: Option<RetryPolicy>,
+    time_sync: TimeSync,
+    middleware: MiddlewareChain,
 }
 
 impl Client {
@@ -28,24 +40,54 @@ impl Client {
     /// Credentials do not need to be specified when using public endpoints
     /// Host is mandatory
     pub fn new(api_key: Option<String>, secret_key: Option<String>, host: String, timeout: Option<u64>) -> Self {
-        let mut builder: reqwest::ClientBuilder = reqwest::ClientBuilder::new();
-        if let Some(timeout_secs) = timeout {
-            builder = builder.timeout(Duration::from_secs(timeout_secs))
-        }
+        let config = Config {
+            timeout,
+            ..Config::default()
+        };
+        Self::new_with_config(api_key, secret_key, host, &config)
+    }
+
+    /// Returns a client for the specified host, sharing the connection pool, rate limiter, retry policy, time sync, credential provider and middlewares of the config
+    pub fn new_with_config(api_key: Option<String>, secret_key: Option<String>, host: String, config: &Config) -> Self {
         Client {
-            // Does it ever make sense for api_key and secret_key to be ""?
-            api_key: api_key.unwrap_or_else(|| "".into()),
-            secret_key: secret_key.unwrap_or_else(|| "".into()),
-            inner: builder.build().unwrap(),
+            credentials: client_credentials(api_key, secret_key, config),
+            signer: SignerCache::new(config.key_type),
+            inner: config.http_pool.clone(),
+            timeout: config.timeout.map(Duration::from_secs),
             host,
+            rate_limiter: config.rate_limiter.clone(),
+            retry_policy: config.retry_policy.clone(),
+            time_sync: config.time_sync.clone(),
+            middleware: config.middleware.clone(),
         }
     }
 
-    pub async fn get_signed<T: DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(endpoint, request);
-        let response = self.inner.get(&url).headers(self.build_headers(true)?).send().await?;
+    pub fn rate_limiter(&self) -> &RateLimiter { &self.rate_limiter }
+
+    pub fn time_sync(&self) -> &TimeSync { &self.time_sync }
 
-        self.handler(response).await
+    /// Seed the rate limits of this client's host, see [`RateLimiter::set_limits`]
+    pub fn set_rate_limits(&self, rate_limits: &[RateLimit]) { self.rate_limiter.set_limits(&self.host, rate_limits) }
+
+    /// Whether the rate limits of this client's host are seeded
+    pub fn has_rate_limits(&self) -> bool { self.rate_limiter.has_limits(&self.host) }
+
+    /// Current rate limit usage of this client's host
+    pub fn rate_limit_usage(&self) -> Vec<RateLimitUsage> { self.rate_limiter.usage(&self.host) }
+
+    pub async fn get_signed<T: DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
+        with_retry(self.retry_policy.as_ref(), &Method::GET, |attempt| async move {
+            // Retries are signed again, the original timestamp may be outside of the receive window
+            let request = if attempt > 0 {
+                refresh_timestamp(request)?
+            } else {
+                request.to_string()
+            };
+            let url = self.sign_request(endpoint, &request)?;
+            self.request(Method::GET, endpoint, &url, Some(self.build_headers(true)?))
+                .await
+        })
+        .await
     }
 
     pub async fn get_signed_d<T: de::DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
@@ -63,10 +105,9 @@ impl Client {
     }
 
     pub async fn post_signed<T: DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(endpoint, request);
-        let response = self.inner.post(&url).headers(self.build_headers(true)?).send().await?;
-
-        self.handler(response).await
+        let url = self.sign_request(endpoint, request)?;
+        self.request(Method::POST, endpoint, &url, Some(self.build_headers(true)?))
+            .await
     }
 
     pub async fn post_signed_d<T: de::DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
@@ -83,6 +124,12 @@ impl Client {
         self.post_signed(endpoint, &request).await
     }
 
+    pub async fn put_signed<T: DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
+        let url = self.sign_request(endpoint, request)?;
+        self.request(Method::PUT, endpoint, &url, Some(self.build_headers(true)?))
+            .await
+    }
+
     pub async fn delete_signed_p<T: de::DeserializeOwned, P: serde::Serialize>(
         &self,
         endpoint: &str,
@@ -94,14 +141,9 @@ impl Client {
     }
 
     pub async fn delete_signed<T: DeserializeOwned>(&self, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(endpoint, request);
-        let response = self
-            .inner
-            .delete(&url)
-            .headers(self.build_headers(true)?)
-            .send()
-            .await?;
-        self.handler(response).await
+        let url = self.sign_request(endpoint, request)?;
+        self.request(Method::DELETE, endpoint, &url, Some(self.build_headers(true)?))
+            .await
     }
 
     pub async fn get<T: DeserializeOwned>(&self, endpoint: &str, request: Option<&str>) -> Result<T> {
@@ -109,9 +151,10 @@ impl Client {
             .map(|r| format!("{}{}?{}", self.host, endpoint, r))
             .unwrap_or_else(|| format!("{}{}", self.host, endpoint));
 
-        let response = self.inner.get(&url).send().await?;
-
-        self.handler(response).await
+        with_retry(self.retry_policy.as_ref(), &Method::GET, |_| {
+            self.request(Method::GET, endpoint, &url, None)
+        })
+        .await
     }
 
     pub async fn get_p<T: DeserializeOwned>(&self, endpoint: &str, request: Option<&str>) -> Result<T> {
@@ -136,9 +179,8 @@ impl Client {
             .map(|s| format!("{}{}?symbol={}", self.host, endpoint, s))
             .unwrap_or_else(|| format!("{}{}", self.host, endpoint));
 
-        let response = self.inner.post(url).headers(self.build_headers(false)?).send().await?;
-
-        self.handler(response).await
+        self.request(Method::POST, endpoint, &url, Some(self.build_headers(false)?))
+            .await
     }
 
     pub async fn put<T: DeserializeOwned>(&self, endpoint: &str, listen_key: &str, symbol: Option<&str>) -> Result<T> {
@@ -147,9 +189,7 @@ impl Client {
             .unwrap_or_else(|| format!("listenKey={listen_key}"));
         let headers = self.build_headers(false)?;
         let url = format!("{}{}?{}", self.host, endpoint, data);
-        let response = self.inner.put(&url).headers(headers).send().await?;
-
-        self.handler(response).await
+        self.request(Method::PUT, endpoint, &url, Some(headers)).await
     }
 
     pub async fn delete<T: DeserializeOwned>(
@@ -162,23 +202,17 @@ impl Client {
             .map(|s| format!("listenKey={listen_key}&symbol={s}"))
             .unwrap_or_else(|| format!("listenKey={listen_key}"));
         let url = format!("{}{}?{}", self.host, endpoint, data);
-        let response = self
-            .inner
-            .delete(url)
-            .headers(self.build_headers(false)?)
-            .send()
-            .await?;
-
-        self.handler(response).await
+        self.request(Method::DELETE, endpoint, &url, Some(self.build_headers(false)?))
+            .await
     }
 
     // Request must be signed
-    fn sign_request(&self, endpoint: &str, request: &str) -> String {
-        let signed_key = hmac::Key::new(hmac::HMAC_SHA256, self.secret_key.as_bytes());
-        let signature = hex_encode(hmac::sign(&signed_key, request.as_bytes()).as_ref());
+    fn sign_request(&self, endpoint: &str, request: &str) -> Result<String> {
+        let request = &self.time_sync.correct_timestamp(request);
+        let signature = self.signer.signer(&self.credentials.credentials()?)?.sign(request)?;
         let url = format!("{}{}?{}&signature={}", self.host, endpoint, request, signature);
 
-        url
+        Ok(url)
     }
 
     fn build_headers(&self, content_type: bool) -> Result<HeaderMap> {
@@ -188,7 +222,7 @@ impl Client {
             // Always include API key
             Some((
                 HeaderName::from_static("x-mbx-apikey"),
-                HeaderValue::from_str(&self.api_key)?,
+                HeaderValue::from_str(self.credentials.credentials()?.api_key())?,
             )),
             // Include content type if needed
             content_type.as_option().map(|_| {
@@ -204,33 +238,224 @@ impl Client {
         Ok(header)
     }
 
-    async fn handler<T: de::DeserializeOwned>(&self, response: Response) -> Result<T> {
-        match response.status() {
-            StatusCode::OK => Ok(response.json().await?),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(Error::InternalServerError),
-            StatusCode::SERVICE_UNAVAILABLE => Err(Error::ServiceUnavailable),
-            StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
-            StatusCode::BAD_REQUEST => {
-                let error: BinanceContentError = response.json().await?;
-                Err(handle_content_error(error))
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        endpoint: &str,
+        url: &str,
+        headers: Option<HeaderMap>,
+    ) -> Result<T> {
+        let request = HttpRequest {
+            method,
+            host: self.host.clone(),
+            endpoint: endpoint.to_string(),
+            url: url.to_string(),
+            headers: headers.unwrap_or_default(),
+        };
+        send(&self.inner, self.timeout, &self.rate_limiter, &self.middleware, request).await
+    }
+}
+
+/// Sends a request through the rate limiter and the middlewares, then maps the response
+async fn send<T: DeserializeOwned>(
+    inner: &HttpPool,
+    timeout: Option<Duration>,
+    rate_limiter: &RateLimiter,
+    middleware: &MiddlewareChain,
+    mut request: HttpRequest,
+) -> Result<T> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "binance.request",
+        method = %request.method,
+        endpoint = %request.endpoint,
+        status = tracing::field::Empty,
+        used_weight = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+    let result = async {
+        rate_limiter
+            .acquire(
+                &request.host,
+                RequestCost::of(&request.method, &request.endpoint, &request.url),
+            )
+            .await?;
+        middleware.before_send(&mut request)?;
+        let started = Instant::now();
+        let mut builder = inner
+            .client()?
+            .request(request.method.clone(), &request.url)
+            .headers(request.headers.clone());
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        let response = builder.send().await?;
+        rate_limiter.update_from_headers(&request.host, response.headers());
+        let response = HttpResponse {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.bytes().await?,
+            elapsed: started.elapsed(),
+        };
+        #[cfg(feature = "tracing")]
+        record_response(&tracing::Span::current(), &response);
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_request(
+            &request.endpoint,
+            request.method.as_str(),
+            Some(response.status.as_u16()),
+            Some(response.elapsed),
+        );
+        middleware.after_receive(&request, &response);
+        handler(&response)
+    };
+    #[cfg(feature = "tracing")]
+    let result = tracing::Instrument::instrument(result, span.clone());
+    let result = result.await;
+    if let Err(e) = &result {
+        #[cfg(feature = "tracing")]
+        span.in_scope(|| tracing::warn!(error = %e, "request failed"));
+        #[cfg(feature = "metrics")]
+        {
+            if matches!(e, Error::ReqError(_)) {
+                crate::metrics::record_request(&request.endpoint, request.method.as_str(), None, None);
             }
-            s => Err(Error::Msg(format!("Received response: {s:?}"))),
+            crate::metrics::record_error(&request.endpoint, e);
         }
+        middleware.on_error(&request, e);
+    }
+    result
+}
+
+#[cfg(feature = "tracing")]
+fn record_response(span: &tracing::Span, response: &HttpResponse) {
+    span.record("status", response.status.as_u16());
+    span.record("latency_ms", response.elapsed.as_millis() as u64);
+    let used_weight = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.as_str().starts_with("x-mbx-used-weight"))
+        .and_then(|(_, value)| value.to_str().ok()?.parse::<u64>().ok());
+    if let Some(used_weight) = used_weight {
+        span.record("used_weight", used_weight);
     }
 }
 
-fn handle_content_error(error: BinanceContentError) -> crate::errors::Error {
+fn handler<T: DeserializeOwned>(response: &HttpResponse) -> Result<T> {
+    match response.status {
+        StatusCode::OK => Ok(serde_json::from_slice(&response.body)?),
+        StatusCode::INTERNAL_SERVER_ERROR => Err(Error::InternalServerError),
+        StatusCode::SERVICE_UNAVAILABLE => Err(Error::ServiceUnavailable),
+        StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
+        StatusCode::BAD_REQUEST => {
+            let error: BinanceContentError = serde_json::from_slice(&response.body)?;
+            Err(handle_content_error(error))
+        }
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::IM_A_TEAPOT => Err(rate_limited_error(response)),
+        s => Err(Error::Msg(format!("Received response: {s:?}"))),
+    }
+}
+
+pub(crate) fn handle_content_error(error: BinanceContentError) -> crate::errors::Error {
     match (error.code, error.msg.as_ref()) {
         (-1013, error_messages::INVALID_PRICE) => Error::InvalidPrice,
         (-1125, msg) => Error::InvalidListenKey(msg.to_string()),
+        (-1021, msg) => Error::InvalidTimestamp(msg.to_string()),
+        (-1022, msg) => Error::InvalidSignature(msg.to_string()),
+        (-2013, _) | (-2011, error_messages::UNKNOWN_ORDER) => Error::UnknownOrder {
+            code: error.error_code(),
+            msg: error.msg,
+        },
+        (-2010, error_messages::INSUFFICIENT_BALANCE) => Error::InsufficientBalance {
+            code: error.error_code(),
+            msg: error.msg,
+        },
+        _ if error.error_code().is_insufficient_balance() => Error::InsufficientBalance {
+            code: error.error_code(),
+            msg: error.msg,
+        },
+        _ if error.error_code().is_order_rejection() => Error::OrderRejected {
+            code: error.error_code(),
+            msg: error.msg,
+        },
         _ => Error::BinanceError { response: error },
     }
 }
 
+/// Fixed credentials from the keys given to the constructor, which prevail over the provider of
+/// the config, used when no key is given
+fn client_credentials(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> SharedCredentials {
+    match (&config.credentials, &api_key, &secret_key) {
+        (Some(provider), None, None) => provider.clone(),
+        // Does it ever make sense for api_key and secret_key to be ""?
+        _ => SharedCredentials::new(Credentials::new(
+            api_key.unwrap_or_default(),
+            secret_key.unwrap_or_default(),
+        )),
+    }
+}
+
+/// Maps HTTP 429 and 418 responses to typed errors carrying `Retry-After` and the error code
+fn rate_limited_error(response: &HttpResponse) -> Error {
+    let retry_after = retry_after(&response.headers);
+    let code = serde_json::from_slice::<BinanceContentError>(&response.body)
+        .ok()
+        .map(|e| e.code);
+    if response.status == StatusCode::IM_A_TEAPOT {
+        Error::IpBanned { retry_after, code }
+    } else {
+        Error::TooManyRequests { retry_after, code }
+    }
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Sends a request until it succeeds or the retry policy gives up, `send` receives the attempt number
+async fn with_retry<T, F, Fut>(policy: Option<&RetryPolicy>, method: &Method, mut send: F) -> Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    #[cfg(feature = "tracing")]
+    let span = policy.map(|_| tracing::info_span!("binance.retry", retries = 0));
+    let mut attempt = 0;
+    loop {
+        let result = send(attempt);
+        #[cfg(feature = "tracing")]
+        let result = tracing::Instrument::instrument(result, span.clone().unwrap_or_else(tracing::Span::none));
+        match result.await {
+            Err(e) => match policy.and_then(|p| p.backoff(method, &e, attempt)) {
+                Some(wait) => {
+                    #[cfg(feature = "tracing")]
+                    if let Some(span) = &span {
+                        span.record("retries", attempt + 1);
+                        span.in_scope(|| tracing::info!(error = %e, ?wait, "retrying request"));
+                    }
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
 pub struct GenericClient {
-    api_key: String,
-    secret_key: String,
-    inner: reqwest::Client,
+    credentials: SharedCredentials,
+    signer: SignerCache,
+    inner: HttpPool,
+    timeout: Option<Duration>,
+    rate_limiter: RateLimiter,
+    retry_policy: Option<RetryPolicy>,
+    time_sync: TimeSync,
+    middleware: MiddlewareChain,
 }
 
 impl GenericClient {
@@ -238,18 +463,31 @@ impl GenericClient {
     /// Credentials do not need to be specified when using public endpoints
     /// Host is mandatory
     pub fn new(api_key: Option<String>, secret_key: Option<String>, timeout: Option<u64>) -> Self {
-        let mut builder: reqwest::ClientBuilder = reqwest::ClientBuilder::new();
-        if let Some(timeout_secs) = timeout {
-            builder = builder.timeout(Duration::from_secs(timeout_secs))
-        }
+        let config = Config {
+            timeout,
+            ..Config::default()
+        };
+        Self::new_with_config(api_key, secret_key, &config)
+    }
+
+    /// Returns a client sharing the connection pool, rate limiter, retry policy, time sync, credential provider and middlewares of the config
+    pub fn new_with_config(api_key: Option<String>, secret_key: Option<String>, config: &Config) -> Self {
         GenericClient {
-            // Does it ever make sense for api_key and secret_key to be ""?
-            api_key: api_key.unwrap_or_else(|| "".into()),
-            secret_key: secret_key.unwrap_or_else(|| "".into()),
-            inner: builder.build().unwrap(),
+            credentials: client_credentials(api_key, secret_key, config),
+            signer: SignerCache::new(config.key_type),
+            inner: config.http_pool.clone(),
+            timeout: config.timeout.map(Duration::from_secs),
+            rate_limiter: config.rate_limiter.clone(),
+            retry_policy: config.retry_policy.clone(),
+            time_sync: config.time_sync.clone(),
+            middleware: config.middleware.clone(),
         }
     }
 
+    pub fn rate_limiter(&self) -> &RateLimiter { &self.rate_limiter }
+
+    pub fn time_sync(&self) -> &TimeSync { &self.time_sync }
+
     pub fn new_with_secrets() -> eyre::Result<Self> {
         let api = env::var("BINANCE_API_KEY")?;
         let secret = env::var("BINANCE_API_SECRET_KEY")?;
@@ -257,10 +495,18 @@ impl GenericClient {
     }
 
     pub async fn get_signed<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(host, endpoint, request);
-        let response = self.inner.get(&url).headers(self.build_headers(true)?).send().await?;
-
-        self.handler(response).await
+        with_retry(self.retry_policy.as_ref(), &Method::GET, |attempt| async move {
+            // Retries are signed again, the original timestamp may be outside of the receive window
+            let request = if attempt > 0 {
+                refresh_timestamp(request)?
+            } else {
+                request.to_string()
+            };
+            let url = self.sign_request(host, endpoint, &request)?;
+            self.request(host, Method::GET, endpoint, &url, Some(self.build_headers(true)?))
+                .await
+        })
+        .await
     }
 
     pub async fn get_signed_d<T: de::DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
@@ -279,10 +525,9 @@ impl GenericClient {
     }
 
     pub async fn post_signed<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(host, endpoint, request);
-        let response = self.inner.post(&url).headers(self.build_headers(true)?).send().await?;
-
-        self.handler(response).await
+        let url = self.sign_request(host, endpoint, request)?;
+        self.request(host, Method::POST, endpoint, &url, Some(self.build_headers(true)?))
+            .await
     }
 
     pub async fn post_signed_d<T: de::DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
@@ -312,14 +557,9 @@ impl GenericClient {
     }
 
     pub async fn delete_signed<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(host, endpoint, request);
-        let response = self
-            .inner
-            .delete(&url)
-            .headers(self.build_headers(true)?)
-            .send()
-            .await?;
-        self.handler(response).await
+        let url = self.sign_request(host, endpoint, request)?;
+        self.request(host, Method::DELETE, endpoint, &url, Some(self.build_headers(true)?))
+            .await
     }
 
     pub async fn get<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: Option<&str>) -> Result<T> {
@@ -327,9 +567,10 @@ impl GenericClient {
             .map(|r| format!("{}{}?{}", host, endpoint, r))
             .unwrap_or_else(|| format!("{}{}", host, endpoint));
 
-        let response = self.inner.get(&url).send().await?;
-
-        self.handler(response).await
+        with_retry(self.retry_policy.as_ref(), &Method::GET, |_| {
+            self.request(host, Method::GET, endpoint, &url, None)
+        })
+        .await
     }
 
     pub async fn get_p<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: Option<&str>) -> Result<T> {
@@ -355,9 +596,8 @@ impl GenericClient {
             .map(|s| format!("{}{}?symbol={}", host, endpoint, s))
             .unwrap_or_else(|| format!("{}{}", host, endpoint));
 
-        let response = self.inner.post(url).headers(self.build_headers(false)?).send().await?;
-
-        self.handler(response).await
+        self.request(host, Method::POST, endpoint, &url, Some(self.build_headers(false)?))
+            .await
     }
 
     pub async fn put<T: DeserializeOwned>(
@@ -372,9 +612,7 @@ impl GenericClient {
             .unwrap_or_else(|| format!("listenKey={listen_key}"));
         let headers = self.build_headers(false)?;
         let url = format!("{}{}?{}", host, endpoint, data);
-        let response = self.inner.put(&url).headers(headers).send().await?;
-
-        self.handler(response).await
+        self.request(host, Method::PUT, endpoint, &url, Some(headers)).await
     }
 
     pub async fn put_signed_p<T: de::DeserializeOwned, P: serde::Serialize>(
@@ -389,10 +627,9 @@ impl GenericClient {
     }
 
     pub async fn put_signed<T: DeserializeOwned>(&self, host: &str, endpoint: &str, request: &str) -> Result<T> {
-        let url = self.sign_request(host, endpoint, request);
-        let response = self.inner.put(url).headers(self.build_headers(true)?).send().await?;
-
-        self.handler(response).await
+        let url = self.sign_request(host, endpoint, request)?;
+        self.request(host, Method::PUT, endpoint, &url, Some(self.build_headers(true)?))
+            .await
     }
 
     pub async fn delete<T: DeserializeOwned>(
@@ -406,23 +643,17 @@ impl GenericClient {
             .map(|s| format!("listenKey={listen_key}&symbol={s}"))
             .unwrap_or_else(|| format!("listenKey={listen_key}"));
         let url = format!("{}{}?{}", host, endpoint, data);
-        let response = self
-            .inner
-            .delete(url)
-            .headers(self.build_headers(false)?)
-            .send()
-            .await?;
-
-        self.handler(response).await
+        self.request(host, Method::DELETE, endpoint, &url, Some(self.build_headers(false)?))
+            .await
     }
 
     // Request must be signed
-    fn sign_request(&self, host: &str, endpoint: &str, request: &str) -> String {
-        let signed_key = hmac::Key::new(hmac::HMAC_SHA256, self.secret_key.as_bytes());
-        let signature = hex_encode(hmac::sign(&signed_key, request.as_bytes()).as_ref());
+    fn sign_request(&self, host: &str, endpoint: &str, request: &str) -> Result<String> {
+        let request = &self.time_sync.correct_timestamp(request);
+        let signature = self.signer.signer(&self.credentials.credentials()?)?.sign(request)?;
         let url = format!("{}{}?{}&signature={}", host, endpoint, request, signature);
 
-        url
+        Ok(url)
     }
 
     fn build_headers(&self, content_type: bool) -> Result<HeaderMap> {
@@ -432,7 +663,7 @@ impl GenericClient {
             // Always include API key
             Some((
                 HeaderName::from_static("x-mbx-apikey"),
-                HeaderValue::from_str(&self.api_key)?,
+                HeaderValue::from_str(self.credentials.credentials()?.api_key())?,
             )),
             // Include content type if needed
             content_type.as_option().map(|_| {
@@ -463,36 +694,18 @@ impl GenericClient {
     {
         let mut request = build_signed_request_p(payload, recv_window).wrap_err("Failed to build signed request")?;
         if signed {
-            request = self.sign_request(host, endpoint, &request);
+            request = self.sign_request(host, endpoint, &request)?;
         }
-        let response = self
-            .inner
-            .request(method, &request)
-            .headers(self.build_headers(true)?)
-            .send()
-            .await
-            .wrap_err("Failed to send request")?;
-
-        self.handler_check(&response)
+        let request = HttpRequest {
+            method,
+            host: host.to_string(),
+            endpoint: endpoint.to_string(),
+            url: request,
+            headers: self.build_headers(true)?,
+        };
+        send(&self.inner, self.timeout, &self.rate_limiter, &self.middleware, request)
             .await
-            .wrap_err("Request handling failed")?;
-
-        let txt = response.text().await.wrap_err("Failed to read response text")?;
-        serde_json::from_str(&txt).wrap_err("Failed to parse response text into target type")
-    }
-
-    async fn handler_check(&self, response: &Response) -> Result<()> {
-        match response.status() {
-            StatusCode::OK => Ok(()),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(Error::InternalServerError),
-            StatusCode::SERVICE_UNAVAILABLE => Err(Error::ServiceUnavailable),
-            StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
-            // StatusCode::BAD_REQUEST => {
-            //     let error: BinanceContentError = response.json().await?;
-            //     Err(handle_content_error(error))
-            // }
-            s => Err(Error::Msg(format!("Received response: {s:?}"))),
-        }
+            .wrap_err("Request handling failed")
     }
 
     // async fn handler_check<T: de::DeserializeOwned>(&self, response: Response) -> Result<T> {
@@ -514,17 +727,81 @@ impl GenericClient {
     //     serde_json::from_str::<T>(&txt).wrap_err_with(|| format!("Failed to parse response text: {}", txt))
     // }
 
-    async fn handler<T: de::DeserializeOwned>(&self, response: Response) -> Result<T> {
-        match response.status() {
-            StatusCode::OK => Ok(response.json().await?),
-            StatusCode::INTERNAL_SERVER_ERROR => Err(Error::InternalServerError),
-            StatusCode::SERVICE_UNAVAILABLE => Err(Error::ServiceUnavailable),
-            StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
-            StatusCode::BAD_REQUEST => {
-                let error: BinanceContentError = response.json().await?;
-                Err(handle_content_error(error))
+    async fn request<T: DeserializeOwned>(
+        &self,
+        host: &str,
+        method: Method,
+        endpoint: &str,
+        url: &str,
+        headers: Option<HeaderMap>,
+    ) -> Result<T> {
+        let request = HttpRequest {
+            method,
+            host: host.to_string(),
+            endpoint: endpoint.to_string(),
+            url: url.to_string(),
+            headers: headers.unwrap_or_default(),
+        };
+        send(&self.inner, self.timeout, &self.rate_limiter, &self.middleware, request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::BinanceErrorCode;
+
+    fn content_error(body: &str) -> Error { handle_content_error(serde_json::from_str(body).unwrap()) }
+
+    #[test]
+    fn test_handle_content_error() {
+        assert!(matches!(
+            content_error(r#"{"code":-1013,"msg":"Invalid price."}"#),
+            Error::InvalidPrice
+        ));
+        assert!(matches!(
+            content_error(r#"{"code":-1021,"msg":"Timestamp for this request is outside of the recvWindow."}"#),
+            Error::InvalidTimestamp(_)
+        ));
+        let error = content_error(r#"{"code":-2010,"msg":"Account has insufficient balance for requested action."}"#);
+        assert!(error.is_insufficient_balance());
+        assert_eq!(error.code(), Some(BinanceErrorCode::NewOrderRejected));
+        assert!(content_error(r#"{"code":-2019,"msg":"Margin is insufficient."}"#).is_insufficient_balance());
+        assert!(matches!(
+            content_error(r#"{"code":-2011,"msg":"Unknown order sent."}"#),
+            Error::UnknownOrder {
+                code: BinanceErrorCode::CancelRejected,
+                ..
             }
-            s => Err(Error::Msg(format!("Received response: {s:?}"))),
-        }
+        ));
+        assert!(matches!(
+            content_error(
+                r#"{"code":-5022,"msg":"Due to the order could not be executed as maker, the Post Only order will be rejected."}"#
+            ),
+            Error::OrderRejected {
+                code: BinanceErrorCode::GtxOrderReject,
+                ..
+            }
+        ));
+        let error = content_error(r#"{"code":-1003,"msg":"Too many requests."}"#);
+        assert!(matches!(error, Error::BinanceError { .. }));
+        assert!(error.is_rate_limit() && error.is_retryable());
+        assert!(!content_error(r#"{"code":-1121,"msg":"Invalid symbol."}"#).is_retryable());
+        let error = content_error(
+            r#"{"code":-1007,"msg":"Timeout waiting for response from backend server. Send status unknown; execution status unknown."}"#,
+        );
+        assert!(error.is_execution_unknown() && !error.is_retryable());
+    }
+
+    #[test]
+    fn test_constructor_keys_prevail_over_provider() {
+        let config = Config::default().set_credential_provider(Credentials::new("provider_key", "provider_secret"));
+        let credentials = client_credentials(None, None, &config).credentials().unwrap();
+        assert_eq!(credentials.api_key(), "provider_key");
+        let credentials = client_credentials(Some("key".into()), Some("secret".into()), &config)
+            .credentials()
+            .unwrap();
+        assert_eq!(credentials.api_key(), "key");
+        assert_eq!(credentials.secret_key(), "secret");
     }
 }
@@ -6,7 +6,8 @@ use serde_with::skip_serializing_none;
 use crate::client::*;
 use crate::coin_margin::rest_model::*;
 use crate::errors::*;
-use crate::futures::rest_model::PositionSide;
+use crate::futures::account::{build_batch_cancel_request, build_batch_orders_request, ModifyOrderRequest};
+use crate::futures::rest_model::{BatchResult, PositionSide};
 use crate::rest_model::OrderType;
 use crate::util::*;
 
@@ -17,9 +18,10 @@ pub struct CoinAccount {
     pub recv_window: u64,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(rename_all = "camelCase")]
+/// An order to place, signed with the recv window of the account
 #[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct OrderRequest {
     pub symbol: String,
     pub side: OrderSide,
@@ -47,8 +49,6 @@ pub struct OrderRequest {
     pub price_protect: Option<String>,
     #[serde(rename = "newOrderRespType")]
     pub new_order_resp_type: Option<NewOrderRespType>,
-    pub recv_window: Option<i64>,
-    pub timestamp: i64,
 }
 
 /// Order Cancellation and Replace Request
@@ -67,7 +67,7 @@ pub struct CancelReplaceRequest {
     // pub time_in_force: Option<TimeInForce>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum NewOrderRespType {
     ACK,
@@ -118,6 +118,42 @@ impl CoinAccount {
         let request = build_signed_request(parameters, self.recv_window)?;
         self.client.get_signed_d("/dapi/v1/balance", request.as_str()).await
     }
+
+    /// Modify the price or quantity of an open limit order
+    pub async fn modify_order(&self, order: ModifyOrderRequest) -> Result<Transaction> {
+        order.valid()?;
+        let request = build_signed_request_p(order, self.recv_window)?;
+        self.client.put_signed("/dapi/v1/order", &request).await
+    }
+
+    /// Place up to 5 orders, each succeeding or failing on its own
+    pub async fn place_batch_orders(&self, orders: &[OrderRequest]) -> Result<Vec<BatchResult<Transaction>>> {
+        let request = build_batch_orders_request(orders, self.recv_window)?;
+        self.client.post_signed("/dapi/v1/batchOrders", &request).await
+    }
+
+    /// Modify up to 5 open limit orders, each succeeding or failing on its own
+    pub async fn modify_batch_orders(&self, orders: &[ModifyOrderRequest]) -> Result<Vec<BatchResult<Transaction>>> {
+        orders.iter().try_for_each(ModifyOrderRequest::valid)?;
+        let request = build_batch_orders_request(orders, self.recv_window)?;
+        self.client.put_signed("/dapi/v1/batchOrders", &request).await
+    }
+
+    /// Cancel up to 10 orders of `symbol` by ids or client order ids, each succeeding or failing
+    /// on its own. Canceled coin-M orders report `cumBase` where USD-M ones report `cumQuote`,
+    /// hence a [`Transaction`] rather than the USD-M [`crate::futures::rest_model::CanceledOrder`].
+    pub async fn cancel_batch_orders<S>(
+        &self,
+        symbol: S,
+        order_ids: &[u64],
+        orig_client_order_ids: &[String],
+    ) -> Result<Vec<BatchResult<Transaction>>>
+    where
+        S: Into<String>,
+    {
+        let request = build_batch_cancel_request(symbol.into(), order_ids, orig_client_order_ids, self.recv_window)?;
+        self.client.delete_signed("/dapi/v1/batchOrders", &request).await
+    }
 }
 
 #[cfg(test)]
@@ -128,6 +164,125 @@ mod tests {
     use dotenvy::dotenv;
     use reqwest::Method;
     // use serde_json::Value;
+
+    fn transaction(order_id: i64) -> serde_json::Value {
+        serde_json::json!({
+            "clientOrderId": "testOrder", "cumQty": "0", "cumBase": "0", "executedQty": "0",
+            "orderId": order_id, "avgPrice": "0.0", "origQty": "10", "price": "0", "reduceOnly": false,
+            "side": "BUY", "positionSide": "SHORT", "status": "NEW", "stopPrice": "9300",
+            "closePosition": false, "symbol": "BTCUSD_PERP", "pair": "BTCUSD", "timeInForce": "GTC",
+            "type": "TRAILING_STOP_MARKET", "origType": "TRAILING_STOP_MARKET", "activatePrice": "9020",
+            "priceRate": "0.3", "updateTime": 1571110484038i64, "workingType": "CONTRACT_PRICE",
+            "priceProtect": false
+        })
+    }
+
+    /// An account of a local server answering `bodies` to as many requests, whose first lines it
+    /// returns
+    async fn serve_all(bodies: Vec<serde_json::Value>) -> (CoinAccount, tokio::task::JoinHandle<Vec<String>>) {
+        use crate::credentials::Credentials;
+
+        let (url, server) = crate::test_util::serve_bodies(bodies).await;
+        let conf = Config {
+            coinm_rest_api_endpoint: url,
+            ..Config::default()
+        }
+        .set_credential_provider(Credentials::new("key", "secret"));
+        (Binance::new_with_config(None, None, &conf), server)
+    }
+
+    #[tokio::test]
+    async fn test_modify_order() {
+        let (account, server) = serve_all(vec![transaction(22542179)]).await;
+        let transaction = account
+            .modify_order(ModifyOrderRequest {
+                symbol: "BTCUSD_PERP".to_string(),
+                side: OrderSide::Buy,
+                order_id: Some(22542179),
+                quantity: Some(1.0),
+                price: Some(30000.5),
+                ..ModifyOrderRequest::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(transaction.order_id, 22542179);
+        let request_line = server.await.unwrap().remove(0);
+        assert!(request_line.starts_with("PUT /dapi/v1/order?"), "{request_line}");
+        assert!(
+            request_line.contains("symbol=BTCUSD_PERP&side=BUY&orderId=22542179&quantity=1&price=30000.5"),
+            "{request_line}"
+        );
+
+        let invalid = account
+            .modify_order(ModifyOrderRequest {
+                symbol: "BTCUSD_PERP".to_string(),
+                order_id: Some(22542179),
+                price: Some(30000.5),
+                ..ModifyOrderRequest::default()
+            })
+            .await;
+        assert!(matches!(invalid, Err(Error::InvalidOrderError { .. })), "{invalid:?}");
+    }
+
+    #[tokio::test]
+    async fn test_batch_orders() {
+        let (account, server) = serve_all(vec![
+            serde_json::json!([
+                transaction(22542179),
+                { "code": -2022, "msg": "ReduceOnly Order is rejected." }
+            ]),
+            serde_json::json!([transaction(22542179)]),
+        ])
+        .await;
+        let order = |side| OrderRequest {
+            symbol: "BTCUSD_PERP".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Some(1.0),
+            ..OrderRequest::default()
+        };
+        let results = account
+            .place_batch_orders(&[order(OrderSide::Buy), order(OrderSide::Sell)])
+            .await
+            .unwrap();
+        let mut results = results.into_iter().map(BatchResult::into_result);
+        assert_eq!(results.next().unwrap().unwrap().order_id, 22542179);
+        assert!(
+            matches!(results.next().unwrap(), Err(Error::OrderRejected { msg, .. }) if msg == "ReduceOnly Order is rejected.")
+        );
+
+        let canceled = account
+            .cancel_batch_orders("BTCUSD_PERP", &[22542179], &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            canceled.into_iter().next().unwrap().into_result().unwrap().cum_base,
+            0.0
+        );
+
+        let request_lines = server.await.unwrap();
+        assert!(
+            request_lines[0].starts_with("POST /dapi/v1/batchOrders?"),
+            "{}",
+            request_lines[0]
+        );
+        let query = request_lines[0].split(['?', ' ']).nth(2).unwrap();
+        let batch = url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == "batchOrders")
+            .map(|(_, v)| v.into_owned())
+            .unwrap();
+        let batch: serde_json::Value = serde_json::from_str(&batch).unwrap();
+        assert_eq!(batch[0]["side"], "BUY");
+        assert_eq!(batch[1]["type"], "MARKET");
+        assert!(batch[0].get("price").is_none());
+        assert!(batch[0].get("timestamp").is_none());
+        assert!(
+            request_lines[1].starts_with("DELETE /dapi/v1/batchOrders?")
+                && request_lines[1].contains("symbol=BTCUSD_PERP&orderIdList=%5B22542179%5D"),
+            "{}",
+            request_lines[1]
+        );
+    }
     #[tokio::test]
     async fn test_coinm_account_open_order() {
         // setup logger
@@ -1,7 +1,12 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
 use crate::client::*;
 use crate::errors::*;
 use crate::rest_model::ServerTime;
 use crate::rest_model::Success;
+use crate::time_sync::TimeOffset;
 
 use super::rest_model::ExchangeInformation;
 
@@ -19,8 +24,25 @@ impl CoinGeneral {
 
     pub async fn get_server_time(&self) -> Result<ServerTime> { self.client.get_p("/dapi/v1/time", None).await }
 
+    /// Measure the offset between the local clock and the server time, used to correct the
+    /// timestamp of signed requests made by every client sharing this client's config
+    pub async fn sync_server_time(&self) -> Result<TimeOffset> {
+        self.client.time_sync().sync(|| self.get_server_time()).await
+    }
+
+    /// Keep the server time offset up to date in a background task, see [`crate::time_sync::TimeSync::spawn`]
+    pub fn spawn_time_sync(&self, interval: Duration) -> JoinHandle<()> {
+        let general = self.clone();
+        self.client.time_sync().spawn(interval, move || {
+            let general = general.clone();
+            async move { general.get_server_time().await }
+        })
+    }
+
     pub async fn exchange_info(&self) -> Result<ExchangeInformation> {
-        self.client.get_p("/dapi/v1/exchangeInfo", None).await
+        let info: ExchangeInformation = self.client.get_p("/dapi/v1/exchangeInfo", None).await?;
+        self.client.set_rate_limits(&info.rate_limits);
+        Ok(info)
     }
 }
 
@@ -1,6 +1,9 @@
+use serde_json::Value;
+
 use crate::errors::*;
+use crate::rest_model::KlineSummaries;
 use crate::{client::*,
-            futures::rest_model::{FundingRate, HistoryQuery}};
+            futures::rest_model::{ContinuousKlineQuery, FundingRate, HistoryQuery, IndexQuery}};
 
 #[derive(Clone)]
 pub struct CoinMarket {
@@ -38,6 +41,157 @@ impl CoinMarket {
             )
             .await
     }
+
+    /// Returns up to 'limit' klines for given symbol and interval ("1m", "5m", ...)
+    /// The volume is in contracts and the quote asset volume is the base asset volume.
+    /// https://binance-docs.github.io/apidocs/delivery/en/#kline-candlestick-data
+    pub async fn get_klines<S1, S2, S3, S4, S5>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = HistoryQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            symbol: symbol.into(),
+            interval: Some(interval.into()),
+            from_id: None,
+            period: None,
+        };
+        self.klines("/dapi/v1/klines", query).await
+    }
+
+    /// Returns up to 'limit' continuous contract klines for given pair, contract type
+    /// ("PERPETUAL", "CURRENT_QUARTER", "NEXT_QUARTER") and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/delivery/en/#continuous-contract-kline-candlestick-data
+    pub async fn get_continuous_contract_klines<S1, S2, S3, S4, S5, S6>(
+        &self,
+        pair: S1,
+        contract_type: S2,
+        interval: S3,
+        limit: S4,
+        start_time: S5,
+        end_time: S6,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+        S4: Into<u16>,
+        S5: Into<Option<u64>>,
+        S6: Into<Option<u64>>,
+    {
+        let query = ContinuousKlineQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            pair: pair.into(),
+            contract_type: contract_type.into(),
+            interval: interval.into(),
+        };
+        self.klines("/dapi/v1/continuousKlines", query).await
+    }
+
+    /// Returns up to 'limit' index price klines for given pair and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/delivery/en/#index-price-kline-candlestick-data
+    pub async fn get_index_price_klines<S1, S2, S3, S4, S5>(
+        &self,
+        pair: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = IndexQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            pair: pair.into(),
+            interval: Some(interval.into()),
+        };
+        self.klines("/dapi/v1/indexPriceKlines", query).await
+    }
+
+    /// Returns up to 'limit' mark price klines for given symbol and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/delivery/en/#mark-price-kline-candlestick-data
+    pub async fn get_mark_price_klines<S1, S2, S3, S4, S5>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = HistoryQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            symbol: symbol.into(),
+            interval: Some(interval.into()),
+            from_id: None,
+            period: None,
+        };
+        self.klines("/dapi/v1/markPriceKlines", query).await
+    }
+
+    /// Returns up to 'limit' premium index klines for given symbol and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/delivery/en/#premium-index-kline-data
+    pub async fn get_premium_index_klines<S1, S2, S3, S4, S5>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = HistoryQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            symbol: symbol.into(),
+            interval: Some(interval.into()),
+            from_id: None,
+            period: None,
+        };
+        self.klines("/dapi/v1/premiumIndexKlines", query).await
+    }
+
+    async fn klines<Q: serde::Serialize>(&self, endpoint: &str, query: Q) -> Result<KlineSummaries> {
+        let rows: Vec<Vec<Value>> = self.client.get_d(endpoint, Some(query)).await?;
+        KlineSummaries::try_from(rows)
+    }
 }
 
 #[cfg(test)]
@@ -55,4 +209,22 @@ mod tests {
         println!("{:?}", funding_rate);
         assert!(funding_rate.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_coin_market_klines() {
+        let body = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data/coin_klines.json")).unwrap();
+        let (url, server) = crate::test_util::serve_bodies([body]).await;
+        let conf = crate::config::Config {
+            coinm_rest_api_endpoint: url,
+            ..crate::config::Config::default()
+        };
+        let market: CoinMarket = Binance::new_with_config(None, None, &conf);
+        let KlineSummaries::AllKlineSummaries(klines) =
+            market.get_klines("BTCUSD_PERP", "1m", 1u16, None, None).await.unwrap();
+        assert_eq!(klines[0].volume, 40683.0);
+        assert_eq!(klines[0].quote_asset_volume, 111.52860436);
+        let request_line = server.await.unwrap().remove(0);
+        assert!(request_line.starts_with("GET /dapi/v1/klines?"), "{request_line}");
+        assert!(request_line.contains("symbol=BTCUSD_PERP"), "{request_line}");
+    }
 }
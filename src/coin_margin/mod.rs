@@ -3,4 +3,5 @@ pub mod general;
 pub mod market;
 pub mod rest_model;
 pub mod userstream;
+pub mod websockets;
 pub mod ws_model;
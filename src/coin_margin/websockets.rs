@@ -0,0 +1,222 @@
+//! Market streams of coin margined futures, served by [`crate::config::Config::coinm_ws_endpoint`].
+//!
+//! Events are parsed as [`crate::coin_margin::ws_model::MarketEvent`], wrapped in a
+//! [`crate::ws_model::CombinedStreamEvent`] when connecting to several streams.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::handshake::client::Response;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::MaybeTlsStream;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::config::Config;
+use crate::errors::*;
+use crate::websockets::{parse_event, ConnectionEvent, ConnectionHandler, EventStream, SocketHandler, WebSocketSender};
+
+pub fn agg_trade_stream(symbol: &str) -> String { format!("{symbol}@aggTrade") }
+
+/// # Arguments
+///
+/// * `symbol`: the contract symbol, such as `btcusd_perp`
+/// * `update_speed`: 1 or 3 seconds
+pub fn mark_price_stream(symbol: &str, update_speed: u8) -> String { format!("{symbol}@markPrice@{update_speed}s") }
+
+/// # Arguments
+///
+/// * `pair`: the underlying pair, such as `btcusd`
+/// * `update_speed`: 1 or 3 seconds
+pub fn index_price_stream(pair: &str, update_speed: u8) -> String { format!("{pair}@indexPrice@{update_speed}s") }
+
+pub fn kline_stream(symbol: &str, interval: &str) -> String { format!("{symbol}@kline_{interval}") }
+
+/// # Arguments
+///
+/// * `pair`: the underlying pair, such as `btcusd`
+/// * `contract_type`: `perpetual`, `current_quarter` or `next_quarter`
+/// * `interval`: the kline interval, such as `1m`
+pub fn continuous_kline_stream(pair: &str, contract_type: &str, interval: &str) -> String {
+    format!("{pair}_{contract_type}@continuousKline_{interval}")
+}
+
+pub fn book_ticker_stream(symbol: &str) -> String { format!("{symbol}@bookTicker") }
+
+pub fn all_book_ticker_stream() -> &'static str { "!bookTicker" }
+
+/// # Arguments
+///
+/// * `symbol`: the contract symbol
+/// * `levels`: 5, 10 or 20
+/// * `update_speed`: 100, 250 or 500
+pub fn partial_book_depth_stream(symbol: &str, levels: u16, update_speed: u16) -> String {
+    format!("{symbol}@depth{levels}@{update_speed}ms")
+}
+
+/// # Arguments
+///
+/// * `symbol`: the contract symbol
+/// * `update_speed`: 100, 250 or 500
+pub fn diff_book_depth_stream(symbol: &str, update_speed: u16) -> String { format!("{symbol}@depth@{update_speed}ms") }
+
+/// Liquidation orders of a symbol
+pub fn liquidation_stream(symbol: &str) -> String { format!("{symbol}@forceOrder") }
+
+/// Liquidation orders of every symbol
+pub fn all_liquidation_stream() -> &'static str { "!forceOrder@arr" }
+
+/// Listings, settlements and bracket changes of every contract
+pub fn contract_info_stream() -> &'static str { "!contractInfo" }
+
+pub struct WebSockets<'a, WE> {
+    pub socket_handler: SocketHandler,
+    handler: Box<dyn FnMut(WE) -> Result<()> + 'a + Send>,
+    connection_handler: Option<ConnectionHandler<'a>>,
+}
+
+impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
+    /// New websocket holder with default configuration
+    pub fn new<Callback>(handler: Callback) -> WebSockets<'a, WE>
+    where
+        Callback: FnMut(WE) -> Result<()> + 'a + Send,
+    {
+        Self::new_with_options(handler, Config::default())
+    }
+
+    /// New websocket holder with provided configuration
+    pub fn new_with_options<Callback>(handler: Callback, conf: Config) -> WebSockets<'a, WE>
+    where
+        Callback: FnMut(WE) -> Result<()> + 'a + Send,
+    {
+        WebSockets {
+            socket_handler: SocketHandler::new_with_options(conf),
+            handler: Box::new(handler),
+            connection_handler: None,
+        }
+    }
+
+    /// Sets the handler notified when the websocket disconnects and reconnects, see
+    /// [`crate::config::Config::set_reconnect_policy`]
+    pub fn set_connection_handler<Callback>(mut self, handler: Callback) -> Self
+    where
+        Callback: FnMut(ConnectionEvent) -> Result<()> + 'a + Send,
+    {
+        self.connection_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Connect to multiple websocket endpoints
+    /// N.B: WE has to be CombinedStreamEvent
+    pub async fn connect_multiple(&mut self, endpoints: Vec<String>) -> Result<()> {
+        self.socket_handler.connect_multiple_coin_m(endpoints).await
+    }
+
+    /// Connect to a websocket endpoint
+    pub async fn connect(&mut self, endpoint: &str) -> Result<()> { self.socket_handler.connect_coin_m(endpoint).await }
+
+    /// A sender to subscribe to streams or unsubscribe from them while the event loop runs
+    pub fn sender(&mut self) -> WebSocketSender { self.socket_handler.sender() }
+
+    /// Disconnect from the endpoint
+    pub async fn disconnect(&mut self) -> Result<()> { self.socket_handler.disconnect().await }
+
+    pub fn socket(&self) -> &Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)> {
+        self.socket_handler.socket()
+    }
+
+    pub async fn event_loop(&mut self, running: &AtomicBool) -> Result<()> {
+        while running.load(Ordering::Relaxed) {
+            let Some(message) = self
+                .socket_handler
+                .next_message(running, &mut self.connection_handler)
+                .await?
+            else {
+                break;
+            };
+            if let Message::Text(msg) = message {
+                if msg.is_empty() {
+                    return Ok(());
+                }
+                let event: WE = parse_event(&self.socket_handler.stream, msg.as_str())?;
+                (self.handler)(event)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Connect to a stream, returning its events as a [`futures::Stream`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::coin_margin::websockets::{connect_stream, mark_price_stream};
+/// use binance::coin_margin::ws_model::MarketEvent;
+/// use binance::config::Config;
+/// use futures::StreamExt;
+///
+/// # async fn run() -> binance::errors::Result<()> {
+/// let mut events = connect_stream::<MarketEvent>(&mark_price_stream("btcusd_perp", 1), Config::default()).await?;
+/// while let Some(event) = events.next().await {
+///     println!("{:?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect_stream<WE>(endpoint: &str, conf: Config) -> Result<EventStream<WE>>
+where
+    WE: serde::de::DeserializeOwned + Send + 'static,
+{
+    let mut socket_handler = SocketHandler::new_with_options(conf);
+    socket_handler.connect_coin_m(endpoint).await?;
+    Ok(socket_handler.into_stream())
+}
+
+/// Connect to several streams, `WE` has to be a [`crate::ws_model::CombinedStreamEvent`]
+pub async fn connect_streams<WE>(endpoints: Vec<String>, conf: Config) -> Result<EventStream<WE>>
+where
+    WE: serde::de::DeserializeOwned + Send + 'static,
+{
+    let mut socket_handler = SocketHandler::new_with_options(conf);
+    socket_handler.connect_multiple_coin_m(endpoints).await?;
+    Ok(socket_handler.into_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coin_margin::ws_model::MarketEvent;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+
+    #[tokio::test]
+    async fn test_connects_to_coin_m_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut path = String::new();
+            let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                path = request.uri().path().to_string();
+                Ok(response)
+            })
+            .await
+            .unwrap();
+            let event = r#"{"e":"indexPriceUpdate","E":1591261236000,"i":"BTCUSD","p":"9636.57860000"}"#;
+            socket.send(Message::Text(event.to_string())).await.unwrap();
+            socket.next().await;
+            path
+        });
+
+        let conf = Config::default().set_coinm_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut events = connect_stream::<MarketEvent>(&index_price_stream("btcusd", 1), conf)
+            .await
+            .unwrap();
+        let event = events.next().await.unwrap().unwrap();
+        assert!(matches!(event, MarketEvent::IndexPrice(e) if e.pair == "BTCUSD"));
+        events.sender().close().await.unwrap();
+        assert!(events.next().await.is_none());
+        assert_eq!(server.await.unwrap(), "/ws/btcusd@indexPrice@1s");
+    }
+}
@@ -9,6 +9,7 @@ use std::primitive::str;
 use super::rest_model::OrderSide;
 use super::rest_model::OrderStatus;
 use super::rest_model::TimeInForce;
+use super::rest_model::{Asks, Bids, ContractStatus, ContractType};
 pub use crate::rest_model::{string_or_float, string_or_float_opt};
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -21,6 +22,266 @@ pub enum UserStreamEvent {
     ListenKeyExpired(ListenKeyExpiredEvent),
 }
 
+/// Events of the market streams, see [`crate::coin_margin::websockets`]
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(tag = "e")]
+pub enum MarketEvent {
+    #[serde(rename = "aggTrade")]
+    AggTrade(Box<AggTradeEvent>),
+    #[serde(rename = "markPriceUpdate")]
+    MarkPrice(Box<MarkPriceEvent>),
+    #[serde(rename = "indexPriceUpdate")]
+    IndexPrice(Box<IndexPriceEvent>),
+    #[serde(rename = "kline")]
+    Kline(Box<KlineEvent>),
+    #[serde(rename = "continuous_kline")]
+    ContinuousKline(Box<ContinuousKlineEvent>),
+    #[serde(rename = "bookTicker")]
+    BookTicker(Box<BookTickerEvent>),
+    #[serde(rename = "depthUpdate")]
+    DepthOrderBook(Box<DepthOrderBookEvent>),
+    #[serde(rename = "forceOrder")]
+    Liquidation(Box<LiquidationEvent>),
+    #[serde(rename = "contractInfo")]
+    ContractInfo(Box<ContractInfoEvent>),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AggTradeEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "a")]
+    pub aggregated_trade_id: u64,
+    #[serde(rename = "p", with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    /// Quantity in contracts
+    #[serde(rename = "q", with = "rust_decimal::serde::str")]
+    pub qty: Decimal,
+    #[serde(rename = "f")]
+    pub first_trade_id: u64,
+    #[serde(rename = "l")]
+    pub last_trade_id: u64,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MarkPriceEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", with = "rust_decimal::serde::str")]
+    pub mark_price: Decimal,
+    /// Only meaningful in the last hour before settlement
+    #[serde(rename = "P", with = "rust_decimal::serde::str")]
+    pub estimated_settle_price: Decimal,
+    #[serde(rename = "i", with = "rust_decimal::serde::str")]
+    pub index_price: Decimal,
+    /// Empty for delivery contracts
+    #[serde(rename = "r")]
+    pub funding_rate: String,
+    /// 0 for delivery contracts
+    #[serde(rename = "T")]
+    pub next_funding_time: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexPriceEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "i")]
+    pub pair: String,
+    #[serde(rename = "p", with = "rust_decimal::serde::str")]
+    pub index_price: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContinuousKlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "ct")]
+    pub contract_type: ContractType,
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+/// Volumes are in contracts, base volumes in the base asset
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Kline {
+    #[serde(rename = "t")]
+    pub start_time: i64,
+    #[serde(rename = "T")]
+    pub end_time: i64,
+    /// Absent from continuous klines
+    #[serde(rename = "s")]
+    pub symbol: Option<String>,
+    #[serde(rename = "i")]
+    pub interval: String,
+    #[serde(rename = "f")]
+    pub first_trade_id: i64,
+    #[serde(rename = "L")]
+    pub last_trade_id: i64,
+    #[serde(rename = "o", with = "rust_decimal::serde::str")]
+    pub open: Decimal,
+    #[serde(rename = "c", with = "rust_decimal::serde::str")]
+    pub close: Decimal,
+    #[serde(rename = "h", with = "rust_decimal::serde::str")]
+    pub high: Decimal,
+    #[serde(rename = "l", with = "rust_decimal::serde::str")]
+    pub low: Decimal,
+    #[serde(rename = "v", with = "rust_decimal::serde::str")]
+    pub volume: Decimal,
+    #[serde(rename = "n")]
+    pub number_of_trades: i64,
+    #[serde(rename = "x")]
+    pub is_final_bar: bool,
+    #[serde(rename = "q", with = "rust_decimal::serde::str")]
+    pub base_volume: Decimal,
+    #[serde(rename = "V", with = "rust_decimal::serde::str")]
+    pub taker_buy_volume: Decimal,
+    #[serde(rename = "Q", with = "rust_decimal::serde::str")]
+    pub taker_buy_base_volume: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookTickerEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "u")]
+    pub update_id: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "b", with = "rust_decimal::serde::str")]
+    pub best_bid: Decimal,
+    #[serde(rename = "B", with = "rust_decimal::serde::str")]
+    pub best_bid_qty: Decimal,
+    #[serde(rename = "a", with = "rust_decimal::serde::str")]
+    pub best_ask: Decimal,
+    #[serde(rename = "A", with = "rust_decimal::serde::str")]
+    pub best_ask_qty: Decimal,
+}
+
+/// Event of both the partial and the diff depth streams
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DepthOrderBookEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "pu")]
+    pub previous_final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<Bids>,
+    #[serde(rename = "a")]
+    pub asks: Vec<Asks>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "o")]
+    pub order: LiquidationOrder,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationOrder {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "S")]
+    pub side: OrderSide,
+    #[serde(rename = "o")]
+    pub order_type: OrderType,
+    #[serde(rename = "f")]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "q", with = "rust_decimal::serde::str")]
+    pub original_quantity: Decimal,
+    #[serde(rename = "p", with = "rust_decimal::serde::str")]
+    pub price: Decimal,
+    #[serde(rename = "ap", with = "rust_decimal::serde::str")]
+    pub average_price: Decimal,
+    #[serde(rename = "X")]
+    pub order_status: OrderStatus,
+    #[serde(rename = "l", with = "rust_decimal::serde::str")]
+    pub last_filled_quantity: Decimal,
+    #[serde(rename = "z", with = "rust_decimal::serde::str")]
+    pub filled_accumulated_quantity: Decimal,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+}
+
+/// Sent when a contract is listed, settled or delisted, or when its notional brackets change
+#[skip_serializing_none]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractInfoEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "ct")]
+    pub contract_type: ContractType,
+    #[serde(rename = "dt")]
+    pub delivery_date: u64,
+    #[serde(rename = "ot")]
+    pub onboard_date: u64,
+    #[serde(rename = "cs")]
+    pub contract_status: ContractStatus,
+    /// Only sent when the brackets change
+    #[serde(rename = "bks")]
+    pub brackets: Option<Vec<NotionalBracket>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotionalBracket {
+    #[serde(rename = "bs")]
+    pub bracket: u32,
+    #[serde(rename = "bnf")]
+    pub notional_floor: f64,
+    #[serde(rename = "bnc")]
+    pub notional_cap: f64,
+    #[serde(rename = "mmr")]
+    pub maintenance_ratio: f64,
+    #[serde(rename = "cf")]
+    pub cumulative: f64,
+    #[serde(rename = "mi")]
+    pub min_leverage: u32,
+    #[serde(rename = "ma")]
+    pub max_leverage: u32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListenKeyExpiredEvent {
     #[serde(rename = "E")]
@@ -251,7 +512,7 @@ mod tests {
 
     use serde_json::Value;
 
-    use crate::coin_margin::ws_model::UserStreamEvent;
+    use crate::coin_margin::ws_model::{MarketEvent, UserStreamEvent};
 
     #[test]
     fn test_deserialize_userstream() {
@@ -283,4 +544,17 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_deserialize_market_streams() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("test_data/coin_market_stream.json");
+        let fc = std::fs::read_to_string(d).unwrap();
+
+        let events: Vec<MarketEvent> = serde_json::from_str(&fc).unwrap();
+        assert_eq!(events.len(), 9);
+        assert!(matches!(&events[4], MarketEvent::ContinuousKline(e) if e.kline.symbol.is_none()));
+        assert!(matches!(&events[6], MarketEvent::DepthOrderBook(e) if e.asks.len() == 2));
+        assert!(matches!(&events[8], MarketEvent::ContractInfo(e) if e.brackets.is_some()));
+    }
 }
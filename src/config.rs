@@ -1,12 +1,23 @@
+use crate::credentials::{CredentialProvider, SharedCredentials};
+use crate::errors::Result;
+use crate::http_pool::{HttpOptions, HttpPool};
+use crate::middleware::{Middleware, MiddlewareChain};
+use crate::rate_limit::{RateLimitPolicy, RateLimiter, RetryPolicy};
+use crate::signer::KeyType;
+use crate::time_sync::TimeSync;
+use crate::websockets::ReconnectPolicy;
+
 pub static DATA_REST_ENDPOINT: &str = "https://api.binance.com";
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     pub rest_api_endpoint: String,
     pub ws_endpoint: String,
+    pub ws_api_endpoint: String,
 
     pub futures_rest_api_endpoint: String,
     pub futures_ws_endpoint: String,
+    pub futures_ws_api_endpoint: String,
 
     pub coinm_rest_api_endpoint: String,
     pub coinm_ws_endpoint: String,
@@ -16,6 +27,22 @@ pub struct Config {
     pub binance_us_api: bool,
 
     pub timeout: Option<u64>,
+
+    pub rate_limiter: RateLimiter,
+
+    pub retry_policy: Option<RetryPolicy>,
+
+    pub time_sync: TimeSync,
+
+    pub key_type: KeyType,
+
+    pub credentials: Option<SharedCredentials>,
+
+    pub http_pool: HttpPool,
+
+    pub middleware: MiddlewareChain,
+
+    pub reconnect_policy: Option<ReconnectPolicy>,
 }
 
 impl Config {
@@ -29,8 +56,10 @@ impl Config {
         Config::default()
             .set_rest_api_endpoint("https://testnet.binance.vision")
             .set_ws_endpoint("wss://testnet.binance.vision")
+            .set_ws_api_endpoint("wss://ws-api.testnet.binance.vision/ws-api/v3")
             .set_futures_rest_api_endpoint("https://testnet.binancefuture.com")
             .set_futures_ws_endpoint("wss://fstream.binancefuture.com")
+            .set_futures_ws_api_endpoint("wss://testnet.binancefuture.com/ws-fapi/v1")
     }
 
     /// Sets the rest api endpoint. Defaults to <https://api.binance.com>.
@@ -73,6 +102,26 @@ impl Config {
         self
     }
 
+    /// Sets the websocket API endpoint. Defaults to "wss://ws-api.binance.com:443/ws-api/v3".
+    ///
+    /// # Arguments
+    ///
+    /// * `ws_api_endpoint`:
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let config = Config::default();
+    /// config.set_ws_api_endpoint("ws://myendpoint:8080");
+    /// ```
+    pub fn set_ws_api_endpoint<T: Into<String>>(mut self, ws_api_endpoint: T) -> Self {
+        self.ws_api_endpoint = ws_api_endpoint.into();
+        self
+    }
+
     /// Sets the futures rest api endpoint. Defaults to <https://fapi.binance.com>.
     ///
     /// # Arguments
@@ -113,6 +162,46 @@ impl Config {
         self
     }
 
+    /// Sets the futures websocket API endpoint. Defaults to "wss://ws-fapi.binance.com/ws-fapi/v1".
+    ///
+    /// # Arguments
+    ///
+    /// * `futures_ws_api_endpoint`:
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let config = Config::default();
+    /// config.set_futures_ws_api_endpoint("ws://myendpoint:8080");
+    /// ```
+    pub fn set_futures_ws_api_endpoint<T: Into<String>>(mut self, futures_ws_api_endpoint: T) -> Self {
+        self.futures_ws_api_endpoint = futures_ws_api_endpoint.into();
+        self
+    }
+
+    /// Sets the coin margined futures websocket endpoint. Defaults to "wss://dstream.binance.com".
+    ///
+    /// # Arguments
+    ///
+    /// * `coinm_ws_endpoint`:
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let config = Config::default();
+    /// config.set_coinm_ws_endpoint("ws://myendpoint:8080");
+    /// ```
+    pub fn set_coinm_ws_endpoint<T: Into<String>>(mut self, coinm_ws_endpoint: T) -> Self {
+        self.coinm_ws_endpoint = coinm_ws_endpoint.into();
+        self
+    }
+
     /// Sets the 'receive window'. The receive window is the number of milliseconds after timestamp
     /// the request is valid for.
     ///
@@ -134,7 +223,8 @@ impl Config {
         self
     }
 
-    /// Sets the client timeout
+    /// Sets the timeout of the requests, applied to each request so that the connection pool
+    /// stays shared
     ///
     /// # Arguments
     ///
@@ -153,6 +243,233 @@ impl Config {
         self.timeout = Some(timeout);
         self
     }
+
+    /// Sets the policy applied when a request would exceed a rate limit. Defaults to
+    /// [`RateLimitPolicy::Delay`].
+    ///
+    /// # Arguments
+    ///
+    /// * `policy`: Delay, reject or only track requests over the limit
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::rate_limit::RateLimitPolicy;
+    /// let config = Config::default();
+    /// config.set_rate_limit_policy(RateLimitPolicy::Reject);
+    /// ```
+    pub fn set_rate_limit_policy(mut self, policy: RateLimitPolicy) -> Self {
+        self.rate_limiter = RateLimiter::new(policy);
+        self
+    }
+
+    /// Sets the rate limiter, to share usage tracking with clients built from another config.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limiter`: The shared rate limiter
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let spot = Config::default();
+    /// let testnet = Config::testnet().set_rate_limiter(spot.rate_limiter.clone());
+    /// ```
+    pub fn set_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Enables retries of GET requests rejected with HTTP 429 or 418, waiting out the
+    /// `Retry-After` window. Orders and other non GET requests are never retried. Disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy`: The number of retries and the longest wait accepted
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::rate_limit::RetryPolicy;
+    /// let config = Config::default();
+    /// config.set_retry_policy(RetryPolicy::default());
+    /// ```
+    pub fn set_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets the time sync used to correct the timestamp of signed requests, to share the measured
+    /// server time offset with clients built from another config.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_sync`: The shared time sync
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let spot = Config::default();
+    /// let testnet = Config::testnet().set_time_sync(spot.time_sync.clone());
+    /// ```
+    pub fn set_time_sync(mut self, time_sync: TimeSync) -> Self {
+        self.time_sync = time_sync;
+        self
+    }
+
+    /// Sets the type of the API key. With [`KeyType::Ed25519`] or [`KeyType::Rsa`] the secret key
+    /// given to the API constructors is the PEM encoded private key. Defaults to [`KeyType::Hmac`].
+    ///
+    /// # Arguments
+    ///
+    /// * `key_type`: HMAC, Ed25519 or RSA
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::signer::KeyType;
+    /// let config = Config::default();
+    /// config.set_key_type(KeyType::Ed25519);
+    /// ```
+    pub fn set_key_type(mut self, key_type: KeyType) -> Self {
+        self.key_type = key_type;
+        self
+    }
+
+    /// Sets where the API key and secret key come from. When set, the provider is asked for
+    /// credentials on every request needing them by the API structs created without keys, keys
+    /// given to the API constructors, such as `new_with_env` or `new_with_private_key`, prevailing.
+    ///
+    /// # Arguments
+    ///
+    /// * `provider`: Environment variables, a file, a callback, rotating credentials or your own provider
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::credentials::{Credentials, RotatingCredentials};
+    /// let credentials = RotatingCredentials::new(Credentials::new("api_key", "secret_key"));
+    /// let config = Config::default().set_credential_provider(credentials.clone());
+    /// credentials.rotate(Credentials::new("new_api_key", "new_secret_key"));
+    /// ```
+    pub fn set_credential_provider(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.credentials = Some(SharedCredentials::new(provider));
+        self
+    }
+
+    /// Sets the options of the connection pool shared by every API struct created from this config.
+    ///
+    /// # Arguments
+    ///
+    /// * `options`: Pool size, keep-alive, HTTP version, proxy and local address
+    ///
+    /// returns: Result<Config>, failing if the proxy url is invalid or the TLS backend cannot be
+    /// initialized
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::http_pool::HttpOptions;
+    /// let config = Config::default();
+    /// let config = config.set_http_options(HttpOptions {
+    ///     pool_max_idle_per_host: Some(8),
+    ///     proxy: Some("http://localhost:3128".to_string()),
+    ///     ..HttpOptions::default()
+    /// });
+    /// assert!(config.is_ok());
+    /// ```
+    pub fn set_http_options(mut self, options: HttpOptions) -> Result<Self> {
+        self.http_pool = HttpPool::new(options)?;
+        Ok(self)
+    }
+
+    /// Sets the connection pool, to share connections with clients built from another config.
+    ///
+    /// # Arguments
+    ///
+    /// * `http_pool`: The shared connection pool
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// let spot = Config::default();
+    /// let testnet = Config::testnet().set_http_pool(spot.http_pool.clone());
+    /// ```
+    pub fn set_http_pool(mut self, http_pool: HttpPool) -> Self {
+        self.http_pool = http_pool;
+        self
+    }
+
+    /// Adds a middleware called on every request of the clients created from this config,
+    /// after the middlewares added before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `middleware`: Hooks called before sending requests, after receiving responses and on errors
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::middleware::{HttpRequest, HttpResponse, Middleware};
+    /// struct Logger;
+    /// impl Middleware for Logger {
+    ///     fn after_receive(&self, request: &HttpRequest, response: &HttpResponse) {
+    ///         println!("{} {} in {:?}", request.redacted_url(), response.status, response.elapsed);
+    ///     }
+    /// }
+    /// let config = Config::default().add_middleware(Logger);
+    /// ```
+    pub fn add_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Makes websockets connect again to the same streams when they are disconnected, such as by
+    /// the forced disconnection after 24 hours, instead of ending their event loop with an error.
+    /// Disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `reconnect_policy`: The backoff between attempts and how many attempts to make
+    ///
+    /// returns: Config
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use binance::config::Config;
+    /// use binance::websockets::ReconnectPolicy;
+    /// let config = Config::default().set_reconnect_policy(ReconnectPolicy::default());
+    /// ```
+    pub fn set_reconnect_policy(mut self, reconnect_policy: ReconnectPolicy) -> Self {
+        self.reconnect_policy = Some(reconnect_policy);
+        self
+    }
 }
 
 impl Default for Config {
@@ -166,9 +483,11 @@ impl Default for Config {
         Config {
             rest_api_endpoint: "https://api.binance.com".into(),
             ws_endpoint: "wss://stream.binance.com:9443".into(),
+            ws_api_endpoint: "wss://ws-api.binance.com:443/ws-api/v3".into(),
 
             futures_rest_api_endpoint: "https://fapi.binance.com".into(),
             futures_ws_endpoint: "wss://fstream.binance.com".into(),
+            futures_ws_api_endpoint: "wss://ws-fapi.binance.com/ws-fapi/v1".into(),
 
             coinm_rest_api_endpoint: "https://dapi.binance.com".into(),
             coinm_ws_endpoint: "wss://dstream.binance.com".into(),
@@ -177,6 +496,22 @@ impl Default for Config {
             binance_us_api: false,
 
             timeout: None,
+
+            rate_limiter: RateLimiter::default(),
+
+            retry_policy: None,
+
+            time_sync: TimeSync::default(),
+
+            key_type: KeyType::default(),
+
+            credentials: None,
+
+            http_pool: HttpPool::default(),
+
+            middleware: MiddlewareChain::default(),
+
+            reconnect_policy: None,
         }
     }
 }
@@ -0,0 +1,373 @@
+//! Error codes documented by Binance for the spot and derivatives APIs.
+//!
+//! Codes are grouped as in the documentation: `-10xx` are general server or network issues,
+//! `-11xx` request issues, `-20xx` order rejections and `-4xxx`/`-5xxx` futures specific errors.
+//! Codes which are not documented are kept as [`BinanceErrorCode::Unknown`].
+
+use std::fmt;
+
+macro_rules! error_codes {
+    ($($(#[$meta:meta])* $name:ident = $code:literal,)+) => {
+        /// An error code returned by the exchange in the `code` field of an error response
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum BinanceErrorCode {
+            $($(#[$meta])* $name,)+
+            /// A code missing from this list
+            Unknown(i32),
+        }
+
+        impl BinanceErrorCode {
+            pub fn from_code(code: i32) -> Self {
+                match code {
+                    $($code => Self::$name,)+
+                    code => Self::Unknown(code),
+                }
+            }
+
+            /// The numeric code, as returned by the exchange
+            pub fn code(&self) -> i32 {
+                match self {
+                    $(Self::$name => $code,)+
+                    Self::Unknown(code) => *code,
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    /// An unknown error occurred while processing the request
+    UnknownError = -1000,
+    /// Internal error, unable to process the request
+    Disconnected = -1001,
+    /// Not authorized to execute this request
+    Unauthorized = -1002,
+    /// Too much request weight used
+    TooManyRequests = -1003,
+    /// The server is busy
+    ServerBusy = -1004,
+    NoSuchIp = -1005,
+    /// An unexpected response was received from the message bus, execution status unknown
+    UnexpectedResponse = -1006,
+    /// Timeout waiting for the backend server, execution status unknown
+    Timeout = -1007,
+    /// The server is overloaded with other requests
+    ServerOverloaded = -1008,
+    ErrorMessageReceived = -1010,
+    /// This IP cannot access this route
+    NonWhiteListedIp = -1011,
+    /// A filter of the symbol rejected the request, such as an invalid price or quantity
+    FilterFailure = -1013,
+    UnknownOrderComposition = -1014,
+    /// Too many new orders
+    TooManyOrders = -1015,
+    ServiceShuttingDown = -1016,
+    UnsupportedOperation = -1020,
+    /// The timestamp is outside of the receive window or ahead of the server time
+    InvalidTimestamp = -1021,
+    /// The signature of the request is invalid
+    InvalidSignature = -1022,
+    StartTimeGreaterThanEndTime = -1023,
+    CompIdInUse = -1033,
+    /// Too many concurrent connections
+    TooManyConnections = -1034,
+    LoggedOut = -1035,
+    NotFound = -1099,
+    IllegalChars = -1100,
+    TooManyParameters = -1101,
+    MandatoryParamEmptyOrMalformed = -1102,
+    UnknownParam = -1103,
+    UnreadParameters = -1104,
+    ParamEmpty = -1105,
+    ParamNotRequired = -1106,
+    ParamOverflow = -1108,
+    BadAccount = -1109,
+    BadInstrumentType = -1110,
+    BadPrecision = -1111,
+    NoDepth = -1112,
+    WithdrawNotNegative = -1113,
+    TifNotRequired = -1114,
+    InvalidTif = -1115,
+    InvalidOrderType = -1116,
+    InvalidSide = -1117,
+    EmptyNewClientOrderId = -1118,
+    EmptyOrigClientOrderId = -1119,
+    BadInterval = -1120,
+    BadSymbol = -1121,
+    InvalidSymbolStatus = -1122,
+    /// The listen key does not exist
+    InvalidListenKey = -1125,
+    AssetNotSupported = -1126,
+    MoreThanXxHours = -1127,
+    OptionalParamsBadCombo = -1128,
+    InvalidParameter = -1130,
+    BadRecvWindow = -1131,
+    BadStrategyType = -1134,
+    InvalidJson = -1135,
+    InvalidNewOrderRespType = -1136,
+    InvalidTickerType = -1139,
+    InvalidCancelRestrictions = -1145,
+    DuplicateSymbols = -1151,
+    /// The new order was rejected, the message tells why
+    NewOrderRejected = -2010,
+    /// The cancel was rejected, the message tells why
+    CancelRejected = -2011,
+    /// The order does not exist
+    NoSuchOrder = -2013,
+    /// The API key format is invalid
+    BadApiKeyFormat = -2014,
+    /// Invalid API key, IP or permissions for the action
+    RejectedMbxKey = -2015,
+    /// No trading window could be found for the symbol
+    NoTradingWindow = -2016,
+    /// Balance is insufficient
+    BalanceNotSufficient = -2018,
+    /// Margin is insufficient
+    MarginNotSufficient = -2019,
+    UnableToFill = -2020,
+    OrderWouldImmediatelyTrigger = -2021,
+    ReduceOnlyReject = -2022,
+    UserInLiquidation = -2023,
+    PositionNotSufficient = -2024,
+    MaxOpenOrderExceeded = -2025,
+    ReduceOnlyOrderTypeNotSupported = -2026,
+    MaxLeverageRatio = -2027,
+    MinLeverageRatio = -2028,
+    InvalidOrderStatus = -4000,
+    PriceLessThanZero = -4001,
+    PriceGreaterThanMaxPrice = -4002,
+    QtyLessThanZero = -4003,
+    QtyLessThanMinQty = -4004,
+    QtyGreaterThanMaxQty = -4005,
+    StopPriceLessThanZero = -4006,
+    StopPriceGreaterThanMaxPrice = -4007,
+    TickSizeLessThanZero = -4008,
+    MaxPriceLessThanMinPrice = -4009,
+    MaxQtyLessThanMinQty = -4010,
+    StepSizeLessThanZero = -4011,
+    MaxNumOrdersLessThanZero = -4012,
+    PriceLessThanMinPrice = -4013,
+    PriceNotIncreasedByTickSize = -4014,
+    InvalidClientOrderIdLen = -4015,
+    PriceHigherThanMultiplierUp = -4016,
+    MultiplierUpLessThanZero = -4017,
+    MultiplierDownLessThanZero = -4018,
+    CompositeScaleOverflow = -4019,
+    TargetStrategyInvalid = -4020,
+    InvalidDepthLimit = -4021,
+    WrongMarketStatus = -4022,
+    QtyNotIncreasedByStepSize = -4023,
+    PriceLowerThanMultiplierDown = -4024,
+    MultiplierDecimalLessThanZero = -4025,
+    CommissionInvalid = -4026,
+    InvalidAccountType = -4027,
+    InvalidLeverage = -4028,
+    InvalidTickSizePrecision = -4029,
+    InvalidStepSizePrecision = -4030,
+    InvalidWorkingType = -4031,
+    ExceedMaxCancelOrderSize = -4032,
+    InsuranceAccountNotFound = -4033,
+    InvalidBalanceType = -4044,
+    MaxStopOrderExceeded = -4045,
+    NoNeedToChangeMarginType = -4046,
+    ThereExistsOpenOrders = -4047,
+    ThereExistsQuantity = -4048,
+    AddIsolatedMarginReject = -4049,
+    /// Cross balance is insufficient
+    CrossBalanceInsufficient = -4050,
+    /// Isolated balance is insufficient
+    IsolatedBalanceInsufficient = -4051,
+    NoNeedToChangeAutoAddMargin = -4052,
+    AutoAddCrossedMarginReject = -4053,
+    AddIsolatedMarginNoPositionReject = -4054,
+    AmountMustBePositive = -4055,
+    InvalidApiKeyType = -4056,
+    InvalidRsaPublicKey = -4057,
+    MaxPriceTooLarge = -4058,
+    NoNeedToChangePositionSide = -4059,
+    InvalidPositionSide = -4060,
+    PositionSideNotMatch = -4061,
+    ReduceOnlyConflict = -4062,
+    InvalidOptionsRequestType = -4063,
+    InvalidOptionsTimeFrame = -4064,
+    InvalidOptionsAmount = -4065,
+    InvalidOptionsEventType = -4066,
+    PositionSideChangeExistsOpenOrders = -4067,
+    PositionSideChangeExistsQuantity = -4068,
+    InvalidOptionsPremiumFee = -4069,
+    InvalidClOrdIdLen = -4070,
+    InvalidOrderIdLen = -4071,
+    InvalidBatchPlaceOrderSize = -4082,
+    PlaceBatchOrdersFail = -4083,
+    UpcomingMethod = -4084,
+    InvalidNotionalLimitCoef = -4085,
+    InvalidPriceSpreadThreshold = -4086,
+    ReduceOnlyOrderPermission = -4087,
+    NoPlaceOrderPermission = -4088,
+    InvalidContractType = -4104,
+    InvalidClientTranIdLen = -4114,
+    DuplicatedClientTranId = -4115,
+    DuplicatedClientOrderId = -4116,
+    /// The stop order is being triggered, try again later
+    StopOrderTriggering = -4117,
+    ReduceOnlyMarginCheckFailed = -4118,
+    MarketOrderReject = -4131,
+    InvalidActivationPrice = -4135,
+    QuantityExistsWithClosePosition = -4137,
+    ReduceOnlyMustBeTrue = -4138,
+    OrderTypeCannotBeMarket = -4139,
+    InvalidOpeningPositionStatus = -4140,
+    SymbolAlreadyClosed = -4141,
+    StrategyInvalidTriggerPrice = -4142,
+    InvalidPair = -4144,
+    IsolatedLeverageRejectWithPosition = -4161,
+    MinNotional = -4164,
+    InvalidTimeInterval = -4165,
+    PriceHigherThanStopMultiplierUp = -4183,
+    PriceLowerThanStopMultiplierDown = -4184,
+    CoolingOffPeriod = -4192,
+    /// The order would not be filled at once and was rejected, for `FOK` orders
+    FokOrderReject = -5021,
+    /// The order would take liquidity and was rejected, for `GTX` post only orders
+    GtxOrderReject = -5022,
+    /// The symbol is not in trading status, its orders cannot be modified
+    MoveOrderNotAllowed = -5024,
+    /// Only limit orders can be modified
+    LimitOrderOnly = -5025,
+    /// The order was modified too many times
+    ExceedMaxModifyOrderLimit = -5026,
+    /// No need to modify the order, the price and quantity are unchanged
+    SameOrder = -5027,
+    /// The timestamp is outside of the receive window of the matching engine
+    MeRecvWindowReject = -5028,
+    /// The modified order would be under the minimum notional
+    ModificationMinNotional = -5029,
+    InvalidPriceMatch = -5037,
+    UnsupportedOrderTypePriceMatch = -5038,
+    InvalidSelfTradePreventionMode = -5039,
+    /// The good till date is not in the future
+    FutureGoodTillDate = -5040,
+    /// No order book level to match the price of the order
+    BboOrderReject = -5041,
+}
+
+impl BinanceErrorCode {
+    /// Transient errors of requests which were not executed, so that sending the same request
+    /// again may succeed, such as server overloads and rate limits
+    pub fn is_retryable(&self) -> bool {
+        self.is_rate_limit()
+            || matches!(
+                self,
+                Self::Disconnected
+                    | Self::ServerBusy
+                    | Self::ServerOverloaded
+                    | Self::ServiceShuttingDown
+                    | Self::StopOrderTriggering
+            )
+    }
+
+    /// Errors after which the request may or may not have been executed. Orders have to be queried
+    /// before being sent again, which could place them twice otherwise.
+    pub fn is_execution_unknown(&self) -> bool {
+        matches!(self, Self::UnknownError | Self::UnexpectedResponse | Self::Timeout)
+    }
+
+    /// The request weight, order count or connection limit was reached
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(
+            self,
+            Self::TooManyRequests | Self::TooManyOrders | Self::TooManyConnections
+        )
+    }
+
+    pub fn is_insufficient_balance(&self) -> bool {
+        matches!(
+            self,
+            Self::BalanceNotSufficient
+                | Self::MarginNotSufficient
+                | Self::CrossBalanceInsufficient
+                | Self::IsolatedBalanceInsufficient
+        )
+    }
+
+    /// The order was valid but rejected by the matching engine or the risk checks
+    pub fn is_order_rejection(&self) -> bool {
+        matches!(
+            self,
+            Self::NewOrderRejected
+                | Self::CancelRejected
+                | Self::NoTradingWindow
+                | Self::UnableToFill
+                | Self::OrderWouldImmediatelyTrigger
+                | Self::ReduceOnlyReject
+                | Self::UserInLiquidation
+                | Self::PositionNotSufficient
+                | Self::MaxOpenOrderExceeded
+                | Self::ReduceOnlyOrderTypeNotSupported
+                | Self::MaxLeverageRatio
+                | Self::MinLeverageRatio
+                | Self::MaxStopOrderExceeded
+                | Self::ReduceOnlyMarginCheckFailed
+                | Self::MarketOrderReject
+                | Self::MinNotional
+                | Self::FokOrderReject
+                | Self::GtxOrderReject
+                | Self::MoveOrderNotAllowed
+                | Self::ExceedMaxModifyOrderLimit
+                | Self::SameOrder
+                | Self::MeRecvWindowReject
+                | Self::ModificationMinNotional
+                | Self::BboOrderReject
+        )
+    }
+}
+
+impl From<i32> for BinanceErrorCode {
+    fn from(code: i32) -> Self { Self::from_code(code) }
+}
+
+impl fmt::Display for BinanceErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(code) => write!(f, "{code}"),
+            _ => write!(f, "{self:?} ({})", self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_round_trip() {
+        for code in -5100..-999 {
+            assert_eq!(BinanceErrorCode::from_code(code).code(), code);
+        }
+        assert_eq!(BinanceErrorCode::from_code(-2010), BinanceErrorCode::NewOrderRejected);
+        assert_eq!(BinanceErrorCode::from(-1), BinanceErrorCode::Unknown(-1));
+        assert_eq!(
+            BinanceErrorCode::InvalidTimestamp.to_string(),
+            "InvalidTimestamp (-1021)"
+        );
+        assert_eq!(BinanceErrorCode::Unknown(-9999).to_string(), "-9999");
+    }
+
+    #[test]
+    fn test_classification() {
+        assert!(BinanceErrorCode::TooManyOrders.is_rate_limit());
+        assert!(BinanceErrorCode::TooManyOrders.is_retryable());
+        assert!(BinanceErrorCode::ServerOverloaded.is_retryable());
+        assert!(!BinanceErrorCode::InvalidSignature.is_retryable());
+        assert!(!BinanceErrorCode::Timeout.is_retryable());
+        assert!(BinanceErrorCode::Timeout.is_execution_unknown());
+        assert!(!BinanceErrorCode::ServerBusy.is_execution_unknown());
+        assert_eq!(BinanceErrorCode::from_code(-5027), BinanceErrorCode::SameOrder);
+        assert!(BinanceErrorCode::SameOrder.is_order_rejection());
+        assert!(BinanceErrorCode::MarginNotSufficient.is_insufficient_balance());
+        assert!(!BinanceErrorCode::NewOrderRejected.is_insufficient_balance());
+        assert!(BinanceErrorCode::GtxOrderReject.is_order_rejection());
+        assert!(!BinanceErrorCode::Unknown(-3000).is_order_rejection());
+    }
+}
@@ -1,7 +1,11 @@
 use serde_json::Value;
 use std::collections::HashMap;
+use std::time::Duration;
 use thiserror::Error;
 
+pub use crate::error_codes::BinanceErrorCode;
+use crate::rest_model::RateLimitType;
+
 #[derive(Debug, Deserialize, Error)]
 #[error("code: {code}, msg: {msg}")]
 pub struct BinanceContentError {
@@ -12,6 +16,18 @@ pub struct BinanceContentError {
     extra: HashMap<String, Value>,
 }
 
+impl BinanceContentError {
+    pub fn new(code: i32, msg: impl Into<String>) -> Self {
+        Self {
+            code,
+            msg: msg.into(),
+            extra: HashMap::new(),
+        }
+    }
+
+    pub fn error_code(&self) -> BinanceErrorCode { BinanceErrorCode::from_code(self.code) }
+}
+
 /// First errors are technical errors
 /// All unhandled binance content errors are BinanceError
 /// The rest are binance content errors that are properly handled
@@ -51,6 +67,16 @@ pub enum Error {
     InvalidOrderError { msg: String },
     #[error("invalid price")]
     InvalidPrice,
+    #[error("invalid timestamp: {0}")]
+    InvalidTimestamp(String),
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    #[error("unknown order ({code}): {msg}")]
+    UnknownOrder { code: BinanceErrorCode, msg: String },
+    #[error("insufficient balance ({code}): {msg}")]
+    InsufficientBalance { code: BinanceErrorCode, msg: String },
+    #[error("order rejected ({code}): {msg}")]
+    OrderRejected { code: BinanceErrorCode, msg: String },
     #[error("invalid period {0}")]
     InvalidPeriod(String),
     #[error("internal server error")]
@@ -59,13 +85,100 @@ pub enum Error {
     ServiceUnavailable,
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("invalid signing key: {0}")]
+    InvalidSigningKey(String),
+    #[error("credentials unavailable: {0}")]
+    CredentialsUnavailable(String),
+    #[error("http client unavailable: {0}")]
+    HttpClientUnavailable(String),
+    #[error("too many requests (code: {code:?}), retry after {retry_after:?}")]
+    TooManyRequests {
+        retry_after: Option<Duration>,
+        code: Option<i32>,
+    },
+    #[error("ip banned (code: {code:?}), retry after {retry_after:?}")]
+    IpBanned {
+        retry_after: Option<Duration>,
+        code: Option<i32>,
+    },
+    #[error(
+        "{rate_limit_type:?} limit of {limit} per {interval:?} reached ({used} used), retry after {retry_after:?}"
+    )]
+    RateLimitExceeded {
+        rate_limit_type: RateLimitType,
+        interval: Duration,
+        used: u32,
+        limit: u32,
+        retry_after: Duration,
+    },
     #[error("{0}")]
     Msg(String),
 }
 
+impl Error {
+    /// How long to wait before retrying, when the server or the rate limiter said so
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::TooManyRequests { retry_after, .. } | Error::IpBanned { retry_after, .. } => *retry_after,
+            Error::RateLimitExceeded { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// The exchange error code, for errors returned by the exchange
+    pub fn code(&self) -> Option<BinanceErrorCode> {
+        match self {
+            Error::BinanceError { response } => Some(response.error_code()),
+            Error::InvalidListenKey(_) => Some(BinanceErrorCode::InvalidListenKey),
+            Error::InvalidPrice => Some(BinanceErrorCode::FilterFailure),
+            Error::InvalidTimestamp(_) => Some(BinanceErrorCode::InvalidTimestamp),
+            Error::InvalidSignature(_) => Some(BinanceErrorCode::InvalidSignature),
+            Error::UnknownOrder { code, .. }
+            | Error::InsufficientBalance { code, .. }
+            | Error::OrderRejected { code, .. } => Some(*code),
+            Error::TooManyRequests { code, .. } | Error::IpBanned { code, .. } => code.map(BinanceErrorCode::from_code),
+            _ => None,
+        }
+    }
+
+    /// Transient errors of requests which were not executed, after which the same request may
+    /// succeed, see [`BinanceErrorCode::is_retryable`]. Idempotent requests may also be sent again
+    /// after the errors of [`Error::is_execution_unknown`].
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::ReqError(e) => e.is_connect() && !e.is_timeout(),
+            Error::ServiceUnavailable => true,
+            e if e.is_rate_limit() => true,
+            e => e.code().is_some_and(|code| code.is_retryable()),
+        }
+    }
+
+    /// Timeouts and internal errors after which the request may or may not have been executed.
+    /// Orders have to be queried before being sent again, which could place them twice otherwise.
+    pub fn is_execution_unknown(&self) -> bool {
+        match self {
+            Error::ReqError(e) => e.is_timeout(),
+            Error::InternalServerError => true,
+            e => e.code().is_some_and(|code| code.is_execution_unknown()),
+        }
+    }
+
+    /// A rate limit of the exchange or of the local [`crate::rate_limit::RateLimiter`] was reached
+    pub fn is_rate_limit(&self) -> bool {
+        match self {
+            Error::TooManyRequests { .. } | Error::IpBanned { .. } | Error::RateLimitExceeded { .. } => true,
+            e => e.code().is_some_and(|code| code.is_rate_limit()),
+        }
+    }
+
+    pub fn is_insufficient_balance(&self) -> bool { matches!(self, Error::InsufficientBalance { .. }) }
+}
+
 /// Custom error messages
 pub mod error_messages {
     pub const INVALID_PRICE: &str = "Invalid price.";
+    pub const INSUFFICIENT_BALANCE: &str = "Account has insufficient balance for requested action.";
+    pub const UNKNOWN_ORDER: &str = "Unknown order sent.";
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -1,8 +1,14 @@
 use std::collections::BTreeMap;
 
-use super::rest_model::{AccountBalance, AccountInformation, CanceledOrder, ChangeLeverageResponse, Order, OrderType,
-                        Position, PositionSide, Transaction, WorkingType};
+use chrono::{DateTime, Duration, Utc};
+
+use super::rest_model::{AccountBalance, AccountInformation, AdlQuantile, AutoCloseType, BatchResult, CanceledOrder,
+                        ChangeLeverageResponse, CommissionRate, Income, IncomeType, Order, OrderAmendment, OrderType,
+                        Position, PositionSide, Transaction, UserTrade, WorkingType};
+use super::ws_model::PriceMatch;
 use crate::account::OrderCancellation;
+use crate::backfill::{seed_rate_limits, split_window, FUTURES_ALL_ORDERS_PAGE_SIZE, FUTURES_EXCHANGE_INFO,
+                      FUTURES_INCOME_PAGE_SIZE, FUTURES_USER_TRADES_PAGE_SIZE};
 use crate::client::Client;
 use crate::errors::*;
 use crate::rest_model::{OrderSide, TimeInForce};
@@ -77,6 +83,180 @@ pub struct OrderRequest {
     pub new_client_order_id: Option<String>,
 }
 
+/// Maximum number of orders of a batch
+pub static MAX_BATCH_ORDERS: usize = 5;
+
+/// Modification of the price or quantity of an open limit order, identified by `order_id` or
+/// `orig_client_order_id`. The quantity and either the price or the price match are mandatory,
+/// even when unchanged.
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModifyOrderRequest {
+    pub symbol: String,
+    pub side: OrderSide,
+    /// Prevails over `orig_client_order_id` when both are set
+    pub order_id: Option<u64>,
+    pub orig_client_order_id: Option<String>,
+    pub quantity: Option<f64>,
+    pub price: Option<f64>,
+    /// Set instead of `price` to follow the order book
+    pub price_match: Option<PriceMatch>,
+}
+
+impl ModifyOrderRequest {
+    pub fn valid(&self) -> Result<()> {
+        if self.order_id.is_none() && self.orig_client_order_id.is_none() {
+            return Err(Error::InvalidOrderError {
+                msg: "Either order id or orig client order id has to be set".to_string(),
+            });
+        }
+        if self.quantity.is_none() {
+            return Err(Error::InvalidOrderError {
+                msg: "Quantity has to be set".to_string(),
+            });
+        }
+        match (self.price, &self.price_match) {
+            (Some(_), Some(_)) => Err(Error::InvalidOrderError {
+                msg: "Price and price match cannot be both set".to_string(),
+            }),
+            (None, None) => Err(Error::InvalidOrderError {
+                msg: "Either price or price match has to be set".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Amendments of an order, identified by `order_id` or `orig_client_order_id`
+#[derive(Serialize, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderAmendmentRequest {
+    pub symbol: String,
+    pub order_id: Option<u64>,
+    pub orig_client_order_id: Option<String>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    /// Defaults to 50, cannot be greater than 100
+    pub limit: Option<u16>,
+}
+
+/// Orders of `symbol` from `order_id`, or the most recent ones within `start_time` and `end_time`,
+/// at most 7 days apart
+#[derive(Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AllOrdersRequest {
+    pub symbol: String,
+    pub order_id: Option<u64>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    /// Defaults to 500, cannot be greater than 1000
+    pub limit: Option<u16>,
+}
+
+/// Trades of `symbol`, of an order, from `from_id` or within `start_time` and `end_time`, at most
+/// 7 days apart. `from_id` cannot be set along with the times.
+#[derive(Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTradesRequest {
+    pub symbol: String,
+    pub order_id: Option<u64>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    pub from_id: Option<u64>,
+    /// Defaults to 500, cannot be greater than 1000
+    pub limit: Option<u16>,
+}
+
+/// Incomes within `start_time` and `end_time`, of the last 7 days by default
+#[derive(Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomeRequest {
+    pub symbol: Option<String>,
+    pub income_type: Option<IncomeType>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    /// Page of the incomes, from 1
+    pub page: Option<u32>,
+    /// Defaults to 100, cannot be greater than 1000
+    pub limit: Option<u16>,
+}
+
+/// Liquidation and auto-deleveraging orders within `start_time` and `end_time`, at most 7 days
+/// apart
+#[derive(Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ForceOrdersRequest {
+    pub symbol: Option<String>,
+    pub auto_close_type: Option<AutoCloseType>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    /// Defaults to 50, cannot be greater than 100
+    pub limit: Option<u16>,
+}
+
+/// Longest time range of a query of trades, orders or incomes
+static HISTORY_QUERY_INTERVAL_DAYS: i64 = 7;
+
+/// Consecutive time ranges in milliseconds, oldest first, of at most
+/// [`HISTORY_QUERY_INTERVAL_DAYS`] days covering `total_duration`, one range by default, until
+/// `start_from`, now by default
+fn history_windows(start_from: Option<DateTime<Utc>>, total_duration: Option<Duration>) -> Vec<(u64, u64)> {
+    let interval_duration = Duration::days(HISTORY_QUERY_INTERVAL_DAYS);
+    let mut current_period_end = start_from.unwrap_or_else(Utc::now);
+    let end_at = current_period_end - total_duration.unwrap_or(interval_duration);
+    let mut windows = vec![];
+    while current_period_end > end_at {
+        let current_period_start = (current_period_end - interval_duration).max(end_at);
+        windows.push((
+            current_period_start.timestamp_millis() as u64,
+            current_period_end.timestamp_millis() as u64 - 1,
+        ));
+        current_period_end = current_period_start;
+    }
+    windows.reverse();
+    windows
+}
+
+/// The `batchOrders` parameter of the orders, a JSON list of their parameters
+pub(crate) fn build_batch_orders_request<O: serde::Serialize>(orders: &[O], recv_window: u64) -> Result<String> {
+    if orders.is_empty() || orders.len() > MAX_BATCH_ORDERS {
+        return Err(Error::InvalidOrderError {
+            msg: format!("A batch has 1 to {MAX_BATCH_ORDERS} orders, got {}", orders.len()),
+        });
+    }
+    let orders = orders.iter().map(build_params_p).collect::<Result<Vec<_>>>()?;
+    let batch_orders: String =
+        url::form_urlencoded::byte_serialize(serde_json::to_string(&orders)?.as_bytes()).collect();
+    build_signed_request([("batchOrders", batch_orders)], recv_window)
+}
+
+/// The orders of `symbol` to cancel, by ids or by client order ids
+pub(crate) fn build_batch_cancel_request(
+    symbol: String,
+    order_ids: &[u64],
+    orig_client_order_ids: &[String],
+    recv_window: u64,
+) -> Result<String> {
+    let count = order_ids.len() + orig_client_order_ids.len();
+    if count == 0 || count > 10 {
+        return Err(Error::InvalidOrderError {
+            msg: format!("A batch cancels 1 to 10 orders, got {count}"),
+        });
+    }
+    let list = |ids: String| url::form_urlencoded::byte_serialize(ids.as_bytes()).collect::<String>();
+    let mut parameters = vec![("symbol", symbol)];
+    if !order_ids.is_empty() {
+        parameters.push(("orderIdList", list(serde_json::to_string(order_ids)?)));
+    }
+    if !orig_client_order_ids.is_empty() {
+        parameters.push((
+            "origClientOrderIdList",
+            list(serde_json::to_string(orig_client_order_ids)?),
+        ));
+    }
+    build_signed_request(parameters, recv_window)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ChangePositionModeRequest {
@@ -254,6 +434,220 @@ impl FuturesAccount {
         self.client.delete_signed_p("/fapi/v1/order", &o, recv_window).await
     }
 
+    /// Modify the price or quantity of an open limit order
+    pub async fn modify_order(&self, order: ModifyOrderRequest) -> Result<Transaction> {
+        order.valid()?;
+        let request = build_signed_request_p(order, self.recv_window)?;
+        self.client.put_signed("/fapi/v1/order", &request).await
+    }
+
+    /// Price and quantity changes of an order, most recent first
+    pub async fn get_order_amendments(&self, query: OrderAmendmentRequest) -> Result<Vec<OrderAmendment>> {
+        self.client
+            .get_signed_p("/fapi/v1/orderAmendment", Some(query), self.recv_window)
+            .await
+    }
+
+    /// Place up to 5 orders, each succeeding or failing on its own
+    pub async fn place_batch_orders(&self, orders: &[OrderRequest]) -> Result<Vec<BatchResult<Transaction>>> {
+        let request = build_batch_orders_request(orders, self.recv_window)?;
+        self.client.post_signed("/fapi/v1/batchOrders", &request).await
+    }
+
+    /// Modify up to 5 open limit orders, each succeeding or failing on its own
+    pub async fn modify_batch_orders(&self, orders: &[ModifyOrderRequest]) -> Result<Vec<BatchResult<Transaction>>> {
+        orders.iter().try_for_each(ModifyOrderRequest::valid)?;
+        let request = build_batch_orders_request(orders, self.recv_window)?;
+        self.client.put_signed("/fapi/v1/batchOrders", &request).await
+    }
+
+    /// Cancel up to 10 orders of `symbol` by ids or client order ids, each succeeding or failing
+    /// on its own
+    pub async fn cancel_batch_orders<S>(
+        &self,
+        symbol: S,
+        order_ids: &[u64],
+        orig_client_order_ids: &[String],
+    ) -> Result<Vec<BatchResult<CanceledOrder>>>
+    where
+        S: Into<String>,
+    {
+        let request = build_batch_cancel_request(symbol.into(), order_ids, orig_client_order_ids, self.recv_window)?;
+        self.client.delete_signed("/fapi/v1/batchOrders", &request).await
+    }
+
+    /// Get an open order
+    pub async fn get_open_order(&self, order: GetOrderRequest) -> Result<Order> {
+        self.client
+            .get_signed_p("/fapi/v1/openOrder", Some(order), self.recv_window)
+            .await
+    }
+
+    /// Open, filled and canceled orders of a symbol, orders canceled or expired without fill being
+    /// kept 3 days
+    pub async fn get_all_orders(&self, query: AllOrdersRequest) -> Result<Vec<Order>> {
+        self.client
+            .get_signed_p("/fapi/v1/allOrders", Some(query), self.recv_window)
+            .await
+    }
+
+    /// Trades of the account for a symbol
+    pub async fn get_user_trades(&self, query: UserTradesRequest) -> Result<Vec<UserTrade>> {
+        self.client
+            .get_signed_p("/fapi/v1/userTrades", Some(query), self.recv_window)
+            .await
+    }
+
+    /// Realized profits, fees, fundings and transfers of the account
+    pub async fn get_income(&self, query: IncomeRequest) -> Result<Vec<Income>> {
+        self.client
+            .get_signed_p("/fapi/v1/income", Some(query), self.recv_window)
+            .await
+    }
+
+    /// Liquidation and auto-deleveraging orders of the account
+    pub async fn get_force_orders(&self, query: ForceOrdersRequest) -> Result<Vec<Order>> {
+        self.client
+            .get_signed_p("/fapi/v1/forceOrders", Some(query), self.recv_window)
+            .await
+    }
+
+    /// Maker and taker commission rates of the account for a symbol
+    pub async fn get_commission_rate<S>(&self, symbol: S) -> Result<CommissionRate>
+    where
+        S: Into<String>,
+    {
+        self.client
+            .get_signed_p(
+                "/fapi/v1/commissionRate",
+                Some(PairQuery { symbol: symbol.into() }),
+                self.recv_window,
+            )
+            .await
+    }
+
+    /// Auto-deleveraging queue positions of the positions of a symbol, or of every symbol
+    pub async fn get_adl_quantile(&self, symbol: Option<String>) -> Result<Vec<AdlQuantile>> {
+        self.client
+            .get_signed_p(
+                "/fapi/v1/adlQuantile",
+                symbol.map(|symbol| PairQuery { symbol }),
+                self.recv_window,
+            )
+            .await
+    }
+
+    /// Trades of `symbol` over `total_duration`, a week by default, until `start_from`, now by
+    /// default, oldest first. Sends a request per week, the weeks of 1000 trades or more being split in
+    /// halves until each part fits in a request, see [`crate::backfill`].
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// use binance::{api::*, config::*, futures::account::*};
+    /// use chrono::Duration;
+    /// let account: FuturesAccount = Binance::new_with_env(&Config::testnet());
+    /// let trades = tokio_test::block_on(account.user_trades_quick("BTCUSDT", None, Some(Duration::weeks(4))));
+    /// assert!(trades.is_ok(), "{:?}", trades);
+    /// ```
+    pub async fn user_trades_quick<S>(
+        &self,
+        symbol: S,
+        start_from: Option<DateTime<Utc>>,
+        total_duration: Option<Duration>,
+    ) -> Result<Vec<UserTrade>>
+    where
+        S: Into<String>,
+    {
+        seed_rate_limits(&self.client, FUTURES_EXCHANGE_INFO, None).await?;
+        let symbol = symbol.into();
+        let mut result = vec![];
+        for (start_time, end_time) in history_windows(start_from, total_duration) {
+            let trades = split_window(
+                start_time,
+                end_time,
+                FUTURES_USER_TRADES_PAGE_SIZE,
+                |start_time, end_time| {
+                    self.get_user_trades(UserTradesRequest {
+                        symbol: symbol.clone(),
+                        start_time: Some(start_time),
+                        end_time: Some(end_time),
+                        limit: Some(FUTURES_USER_TRADES_PAGE_SIZE),
+                        ..UserTradesRequest::default()
+                    })
+                },
+            )
+            .await?;
+            result.extend(trades);
+        }
+        Ok(result)
+    }
+
+    /// Orders of `symbol` created over `total_duration`, a week by default, until `start_from`,
+    /// now by default, oldest first. Sends a request per week, the weeks of 1000 orders or more
+    /// being split in halves until each part fits in a request since the exchange returns the most
+    /// recent orders of a week, see [`crate::backfill`].
+    pub async fn all_orders_quick<S>(
+        &self,
+        symbol: S,
+        start_from: Option<DateTime<Utc>>,
+        total_duration: Option<Duration>,
+    ) -> Result<Vec<Order>>
+    where
+        S: Into<String>,
+    {
+        seed_rate_limits(&self.client, FUTURES_EXCHANGE_INFO, None).await?;
+        let symbol = symbol.into();
+        let mut result = vec![];
+        for (start_time, end_time) in history_windows(start_from, total_duration) {
+            let orders = split_window(
+                start_time,
+                end_time,
+                FUTURES_ALL_ORDERS_PAGE_SIZE,
+                |start_time, end_time| {
+                    self.get_all_orders(AllOrdersRequest {
+                        symbol: symbol.clone(),
+                        order_id: None,
+                        start_time: Some(start_time),
+                        end_time: Some(end_time),
+                        limit: Some(FUTURES_ALL_ORDERS_PAGE_SIZE),
+                    })
+                },
+            )
+            .await?;
+            result.extend(orders);
+        }
+        Ok(result)
+    }
+
+    /// Incomes matching the symbol and income type of `query` over `total_duration`, a week by
+    /// default, until `start_from`, now by default, oldest first. Sends a request per week and per
+    /// 1000 incomes.
+    pub async fn income_quick(
+        &self,
+        mut query: IncomeRequest,
+        start_from: Option<DateTime<Utc>>,
+        total_duration: Option<Duration>,
+    ) -> Result<Vec<Income>> {
+        seed_rate_limits(&self.client, FUTURES_EXCHANGE_INFO, None).await?;
+        let mut result = vec![];
+        query.limit = Some(FUTURES_INCOME_PAGE_SIZE);
+        for (start_time, end_time) in history_windows(start_from, total_duration) {
+            query.start_time = Some(start_time);
+            query.end_time = Some(end_time);
+            for page in 1.. {
+                query.page = Some(page);
+                let incomes = self.get_income(query.clone()).await?;
+                let last_page = incomes.len() < usize::from(FUTURES_INCOME_PAGE_SIZE);
+                result.extend(incomes);
+                if last_page {
+                    break;
+                }
+            }
+        }
+        result.sort_by_key(|income| income.time);
+        Ok(result)
+    }
+
     /// Get current position risk for the symbol
     pub async fn position_information<S>(&self, symbol: S) -> Result<Vec<Position>>
     where
@@ -300,7 +694,8 @@ impl FuturesAccount {
 
     /// Change the dual position side
     pub async fn change_position_mode(&self, dual_side_position: bool) -> Result<()> {
-        self.client
+        let _: serde_json::Value = self
+            .client
             .post_signed_p(
                 "/fapi/v1/positionSide/dual",
                 ChangePositionModeRequest { dual_side_position },
@@ -353,4 +748,308 @@ mod tests {
 
         assert!(res.is_ok(), "failed second call: {:?}", res);
     }
+
+    #[test]
+    fn test_batch_cancel_request() {
+        let request = build_batch_cancel_request("BTCUSDT".to_string(), &[1, 2], &[], 0).unwrap();
+        assert!(request.contains("symbol=BTCUSDT&orderIdList=%5B1%2C2%5D"), "{request}");
+        assert!(!request.contains("origClientOrderIdList"));
+        assert!(build_batch_cancel_request("BTCUSDT".to_string(), &[], &[], 0).is_err());
+    }
+
+    fn transaction(order_id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "clientOrderId": "testOrder", "cumQty": "0", "cumQuote": "0", "executedQty": "0",
+            "orderId": order_id, "avgPrice": "0.00000", "origQty": "10", "price": "0", "reduceOnly": false,
+            "side": "BUY", "positionSide": "SHORT", "status": "NEW", "stopPrice": "9300",
+            "closePosition": false, "symbol": "BTCUSDT", "timeInForce": "GTC", "type": "TRAILING_STOP_MARKET",
+            "origType": "TRAILING_STOP_MARKET", "activatePrice": "9020", "priceRate": "0.3",
+            "updateTime": 1566818724722u64, "workingType": "CONTRACT_PRICE", "priceProtect": false
+        })
+    }
+
+    /// An account of a local server answering `bodies` to as many requests, whose first lines it
+    /// returns
+    async fn serve_all(bodies: Vec<serde_json::Value>) -> (FuturesAccount, tokio::task::JoinHandle<Vec<String>>) {
+        use crate::api::Binance;
+        use crate::config::Config;
+        use crate::credentials::Credentials;
+
+        let (url, server) = crate::test_util::serve_bodies(bodies).await;
+        let conf = Config::default()
+            .set_futures_rest_api_endpoint(url)
+            .set_credential_provider(Credentials::new("key", "secret"));
+        (Binance::new_with_config(None, None, &conf), server)
+    }
+
+    /// An account of a local server answering `body` to a single request, whose first line it returns
+    async fn serve(body: serde_json::Value) -> (FuturesAccount, tokio::task::JoinHandle<String>) {
+        let (account, server) = serve_all(vec![body]).await;
+        (account, tokio::spawn(async move { server.await.unwrap().remove(0) }))
+    }
+
+    #[tokio::test]
+    async fn test_place_batch_orders() {
+        let (account, server) = serve(serde_json::json!([
+            transaction(22542179),
+            { "code": -2022, "msg": "ReduceOnly Order is rejected." }
+        ]))
+        .await;
+        let order = |side| OrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Some(0.001),
+            ..OrderRequest::default()
+        };
+        let results = account
+            .place_batch_orders(&[order(OrderSide::Buy), order(OrderSide::Sell)])
+            .await
+            .unwrap();
+        let mut results = results.into_iter().map(BatchResult::into_result);
+        assert_eq!(results.next().unwrap().unwrap().order_id, 22542179);
+        assert!(
+            matches!(results.next().unwrap(), Err(Error::OrderRejected { msg, .. }) if msg == "ReduceOnly Order is rejected.")
+        );
+
+        let request_line = server.await.unwrap();
+        let query = request_line.split(['?', ' ']).nth(2).unwrap();
+        let batch = url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == "batchOrders")
+            .map(|(_, v)| v.into_owned())
+            .unwrap();
+        let batch: serde_json::Value = serde_json::from_str(&batch).unwrap();
+        assert_eq!(batch[0]["side"], "BUY");
+        assert_eq!(batch[1]["quantity"], "0.001");
+        assert!(batch[0].get("price").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_modify_order() {
+        let order = |order_id, quantity, price, price_match| ModifyOrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            order_id,
+            quantity,
+            price,
+            price_match,
+            ..ModifyOrderRequest::default()
+        };
+        for invalid in [
+            order(None, Some(10.0), None, Some(PriceMatch::Queue5)),
+            order(Some(22542179), None, None, Some(PriceMatch::Queue5)),
+            order(Some(22542179), Some(10.0), None, None),
+            order(Some(22542179), Some(10.0), Some(30000.0), Some(PriceMatch::Queue5)),
+        ] {
+            assert!(invalid.valid().is_err(), "{invalid:?}");
+        }
+
+        let (account, server) = serve(transaction(22542179)).await;
+        let transaction = account
+            .modify_order(order(Some(22542179), Some(10.0), None, Some(PriceMatch::Queue5)))
+            .await
+            .unwrap();
+        assert_eq!(transaction.order_id, 22542179);
+        let request_line = server.await.unwrap();
+        assert!(request_line.starts_with("PUT /fapi/v1/order?"), "{request_line}");
+        assert!(request_line.contains("orderId=22542179"), "{request_line}");
+        assert!(request_line.contains("priceMatch=QUEUE_5"), "{request_line}");
+    }
+
+    #[tokio::test]
+    async fn test_get_order_amendments() {
+        let (account, server) = serve(serde_json::json!([{
+            "amendmentId": 5363, "symbol": "BTCUSDT", "pair": "BTCUSDT", "orderId": 20072994037u64,
+            "clientOrderId": "LJ9R4QZDihCaS8UAOOLpgW", "time": 1629184560899u64,
+            "amendment": {
+                "price": { "before": "30004", "after": "30003.2" },
+                "origQty": { "before": "1", "after": "1" },
+                "count": 3
+            }
+        }]))
+        .await;
+        let amendments = account
+            .get_order_amendments(OrderAmendmentRequest {
+                symbol: "BTCUSDT".to_string(),
+                order_id: Some(20072994037),
+                ..OrderAmendmentRequest::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(amendments[0].amendment.price.after, 30003.2);
+        assert_eq!(amendments[0].amendment.count, 3);
+        let request_line = server.await.unwrap();
+        assert!(
+            request_line.starts_with("GET /fapi/v1/orderAmendment?"),
+            "{request_line}"
+        );
+    }
+
+    /// Served first to the history helpers, which seed the rate limits from it
+    fn rate_limits() -> serde_json::Value {
+        serde_json::json!({
+            "rateLimits": [{ "rateLimitType": "REQUEST_WEIGHT", "interval": "MINUTE", "intervalNum": 1, "limit": 2400 }]
+        })
+    }
+
+    /// Times of the rows 0 to 600 within the first half of the week until 1_700_001_003_000, of the
+    /// next ones within the second half
+    fn history_time(id: u64) -> u64 {
+        if id < 600 {
+            1_699_396_203_000 + id * 1000
+        } else {
+            1_699_698_603_000 + (id - 600) * 1000
+        }
+    }
+
+    fn user_trades(ids: std::ops::Range<u64>) -> serde_json::Value {
+        ids.map(|id| {
+            serde_json::json!({
+                "buyer": false, "commission": "-0.07819010", "commissionAsset": "USDT", "id": id,
+                "maker": false, "orderId": 25851813, "price": "7819.01", "qty": "0.002",
+                "quoteQty": "15.63802", "realizedPnl": "-0.91539999", "side": "SELL",
+                "positionSide": "SHORT", "symbol": "BTCUSDT", "time": history_time(id)
+            })
+        })
+        .collect()
+    }
+
+    fn orders(ids: std::ops::Range<u64>) -> serde_json::Value {
+        ids.map(|id| {
+            serde_json::json!({
+                "avgPrice": "0.00000", "clientOrderId": "abc", "cumQuote": "0", "executedQty": "0",
+                "orderId": id, "origQty": "0.40", "origType": "LIMIT", "price": "0", "reduceOnly": false,
+                "side": "BUY", "positionSide": "SHORT", "status": "NEW", "stopPrice": "0",
+                "closePosition": false, "symbol": "BTCUSDT", "time": history_time(id), "timeInForce": "GTC",
+                "type": "LIMIT", "updateTime": history_time(id), "workingType": "CONTRACT_PRICE",
+                "priceProtect": false
+            })
+        })
+        .collect()
+    }
+
+    /// The week is split in halves since its page, of the most recent rows, is full
+    fn assert_split_week(request_lines: &[String], path: &str) {
+        assert!(
+            request_lines[0].starts_with("GET /fapi/v1/exchangeInfo "),
+            "{}",
+            request_lines[0]
+        );
+        for (request_line, window) in request_lines[1..].iter().zip([
+            "startTime=1699396203000&endTime=1700001002999",
+            "startTime=1699396203000&endTime=1699698602999",
+            "startTime=1699698603000&endTime=1700001002999",
+        ]) {
+            assert!(request_line.starts_with(path), "{request_line}");
+            assert!(request_line.contains(window), "{request_line}");
+        }
+        assert_eq!(request_lines.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_user_trades_quick() {
+        let (account, server) = serve_all(vec![
+            rate_limits(),
+            user_trades(300..1300),
+            user_trades(0..600),
+            user_trades(600..1300),
+        ])
+        .await;
+        let start_from = DateTime::from_timestamp_millis(1_700_001_003_000).unwrap();
+        let trades = account
+            .user_trades_quick("BTCUSDT", Some(start_from), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            trades.iter().map(|trade| trade.id).collect::<Vec<_>>(),
+            (0..1300).collect::<Vec<_>>()
+        );
+        assert_eq!(trades[0].realized_pnl, -0.91539999);
+        assert_split_week(&server.await.unwrap(), "GET /fapi/v1/userTrades?");
+    }
+
+    #[tokio::test]
+    async fn test_all_orders_quick() {
+        let (account, server) = serve_all(vec![
+            rate_limits(),
+            orders(300..1300),
+            orders(0..600),
+            orders(600..1300),
+        ])
+        .await;
+        let start_from = DateTime::from_timestamp_millis(1_700_001_003_000).unwrap();
+        let orders = account
+            .all_orders_quick("BTCUSDT", Some(start_from), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            orders.iter().map(|order| order.order_id).collect::<Vec<_>>(),
+            (0..1300).collect::<Vec<_>>()
+        );
+        assert_eq!(orders[0].time, 1_699_396_203_000);
+        assert_split_week(&server.await.unwrap(), "GET /fapi/v1/allOrders?");
+    }
+
+    #[tokio::test]
+    async fn test_income_quick() {
+        let income = |time: u64| {
+            serde_json::json!({
+                "symbol": "", "incomeType": "TRANSFER", "income": "-0.37500000", "asset": "USDT",
+                "info": "TRANSFER", "time": time, "tranId": 9689322392u64, "tradeId": ""
+            })
+        };
+        let (account, server) = serve_all(vec![
+            rate_limits(),
+            serde_json::json!([income(1_699_000_000_000)]),
+            serde_json::json!([income(1_700_000_000_000)]),
+        ])
+        .await;
+        let start_from = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        let incomes = account
+            .income_quick(
+                IncomeRequest {
+                    income_type: Some(IncomeType::Transfer),
+                    ..IncomeRequest::default()
+                },
+                Some(start_from),
+                Some(Duration::days(10)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(incomes.len(), 2);
+        assert_eq!(incomes[0].income_type, IncomeType::Transfer);
+
+        let request_lines = server.await.unwrap();
+        assert!(
+            request_lines[0].starts_with("GET /fapi/v1/exchangeInfo "),
+            "{}",
+            request_lines[0]
+        );
+        assert!(
+            request_lines[1].contains("incomeType=TRANSFER&startTime=1699136000000&endTime=1699395199999&page=1"),
+            "{}",
+            request_lines[1]
+        );
+        assert!(
+            request_lines[2].contains("startTime=1699395200000&endTime=1699999999999&page=1"),
+            "{}",
+            request_lines[2]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_adl_quantile() {
+        let (account, server) = serve(serde_json::json!([
+            { "symbol": "ETHUSDT", "adlQuantile": { "LONG": 3, "SHORT": 3, "HEDGE": 0 } },
+            { "symbol": "BTCUSDT", "adlQuantile": { "LONG": 1, "SHORT": 2, "BOTH": 0 } }
+        ]))
+        .await;
+        let quantiles = account.get_adl_quantile(None).await.unwrap();
+        assert_eq!(quantiles[0].adl_quantile.long, Some(3));
+        assert_eq!(quantiles[1].adl_quantile.both, Some(0));
+        assert_eq!(quantiles[1].adl_quantile.hedge, None);
+        let request_line = server.await.unwrap();
+        assert!(request_line.starts_with("GET /fapi/v1/adlQuantile?"), "{request_line}");
+        assert!(!request_line.contains("symbol"), "{request_line}");
+    }
 }
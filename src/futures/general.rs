@@ -1,7 +1,12 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
 use crate::client::*;
 use crate::errors::*;
 use crate::futures::rest_model::*;
 use crate::rest_model::ServerTime;
+use crate::time_sync::TimeOffset;
 
 #[derive(Clone)]
 pub struct FuturesGeneral {
@@ -18,10 +23,28 @@ impl FuturesGeneral {
     /// Check server time
     pub async fn get_server_time(&self) -> Result<ServerTime> { self.client.get_p("/fapi/v1/time", None).await }
 
+    /// Measure the offset between the local clock and the server time, used to correct the
+    /// timestamp of signed requests made by every client sharing this client's config
+    pub async fn sync_server_time(&self) -> Result<TimeOffset> {
+        self.client.time_sync().sync(|| self.get_server_time()).await
+    }
+
+    /// Keep the server time offset up to date in a background task, see [`crate::time_sync::TimeSync::spawn`]
+    pub fn spawn_time_sync(&self, interval: Duration) -> JoinHandle<()> {
+        let general = self.clone();
+        self.client.time_sync().spawn(interval, move || {
+            let general = general.clone();
+            async move { general.get_server_time().await }
+        })
+    }
+
     /// Obtain exchange information
     /// - Current exchange trading rules and symbol information
+    /// - Rate limits, used to seed the client's rate limiter
     pub async fn exchange_info(&self) -> Result<ExchangeInformation> {
-        self.client.get_p("/fapi/v1/exchangeInfo", None).await
+        let info: ExchangeInformation = self.client.get_p("/fapi/v1/exchangeInfo", None).await?;
+        self.client.set_rate_limits(&info.rate_limits);
+        Ok(info)
     }
 
     /// Get Symbol information
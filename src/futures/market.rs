@@ -1,11 +1,14 @@
+use crate::backfill::{paginate, seed_rate_limits, Cursor, FUTURES_AGG_TRADES_PAGE_SIZE, FUTURES_EXCHANGE_INFO,
+                      FUTURES_HISTORICAL_TRADES_PAGE_SIZE, FUTURES_KLINES_PAGE_SIZE};
 use crate::client::*;
 use crate::errors::*;
 use crate::futures::rest_model::*;
 use crate::rest_model::{BookTickers, KlineSummaries, PairAndWindowQuery, PairQuery, SymbolPrice, Tickers};
+use crate::util::build_request;
+use futures::Stream;
 use serde_json::Value;
 
 //TODO : Validate intervals and start/end times in history queries
-//TODO : find out the repartition of kline/candlestick columns in the future kline rows
 //TODO : make limit optional where applicable
 
 #[derive(Clone)]
@@ -25,6 +28,16 @@ impl FuturesMarket {
             .await
     }
 
+    /// Order book at a custom depth, `limit` is one of 5, 10, 20, 50, 100, 500 or 1000
+    pub async fn get_custom_depth<S>(&self, symbol: S, limit: u16) -> Result<OrderBook>
+    where
+        S: AsRef<str>,
+    {
+        let parameters = [("symbol", symbol.as_ref().to_string()), ("limit", limit.to_string())];
+        let request = build_request(parameters);
+        self.client.get("/fapi/v1/depth", Some(&request)).await
+    }
+
     /// Get trades for a pair
     pub async fn get_trades<S>(&self, symbol: S) -> Result<Trades>
     where
@@ -308,26 +321,109 @@ impl FuturesMarket {
             from_id: None,
             period: None,
         };
-        let data: Vec<Vec<Value>> = self.client.get_d("/fapi/v1/klines", Some(query)).await?;
-        unimplemented!("TODO: Parse klines");
-        // let klines = KlineSummaries::AllKlineSummaries(
-        //     data.iter()
-        //         .map(|row| KlineSummary {
-        //             open_time: to_i64(&row[0]),
-        //             open: to_f64(&row[1]),
-        //             high: to_f64(&row[2]),
-        //             low: to_f64(&row[3]),
-        //             close: to_f64(&row[4]),
-        //             volume: to_f64(&row[5]),
-        //             close_time: to_i64(&row[6]),
-        //             quote_asset_volume: to_f64(&row[7]),
-        //             number_of_trades: to_i64(&row[8]),
-        //             taker_buy_base_asset_volume: to_f64(&row[9]),
-        //             taker_buy_quote_asset_volume: to_f64(&row[10]),
-        //         })
-        //         .collect(),
-        // );
-        // Ok(klines)
+        self.klines("/fapi/v1/klines", query).await
+    }
+
+    /// Historical trades of `symbol` from the trade `from_id` to `end_time`, or to now, fetched
+    /// page by page, see [`crate::backfill`]
+    pub fn backfill_historical_trades<S1, S2>(
+        &self,
+        symbol: S1,
+        from_id: u64,
+        end_time: S2,
+    ) -> impl Stream<Item = Result<Trade>> + Send + 'static
+    where
+        S1: Into<String>,
+        S2: Into<Option<u64>>,
+    {
+        let market = self.clone();
+        let symbol = symbol.into();
+        paginate(
+            from_id,
+            |trade: &Trade| trade.id + 1,
+            end_time.into(),
+            FUTURES_HISTORICAL_TRADES_PAGE_SIZE,
+            move |from_id| {
+                let (market, symbol) = (market.clone(), symbol.clone());
+                async move {
+                    seed_rate_limits(&market.client, FUTURES_EXCHANGE_INFO, None).await?;
+                    let Trades::AllTrades(trades) = market
+                        .get_historical_trades(symbol, from_id, FUTURES_HISTORICAL_TRADES_PAGE_SIZE)
+                        .await?;
+                    Ok(trades)
+                }
+            },
+        )
+    }
+
+    /// Aggregated trades of `symbol` from `start_time` to `end_time`, or to now, fetched page by
+    /// page, see [`crate::backfill`]
+    pub fn backfill_agg_trades<S1, S2>(
+        &self,
+        symbol: S1,
+        start_time: u64,
+        end_time: S2,
+    ) -> impl Stream<Item = Result<AggTrade>> + Send + 'static
+    where
+        S1: Into<String>,
+        S2: Into<Option<u64>>,
+    {
+        let market = self.clone();
+        let symbol = symbol.into();
+        paginate(
+            Cursor::StartTime(start_time),
+            |trade: &AggTrade| Cursor::FromId(trade.agg_id + 1),
+            end_time.into(),
+            FUTURES_AGG_TRADES_PAGE_SIZE,
+            move |cursor| {
+                let (market, symbol) = (market.clone(), symbol.clone());
+                async move {
+                    seed_rate_limits(&market.client, FUTURES_EXCHANGE_INFO, None).await?;
+                    let (from_id, start_time) = match cursor {
+                        Cursor::StartTime(start_time) => (None, Some(start_time)),
+                        Cursor::FromId(from_id) => (Some(from_id), None),
+                    };
+                    let AggTrades::AllAggTrades(trades) = market
+                        .get_agg_trades(symbol, from_id, start_time, None, FUTURES_AGG_TRADES_PAGE_SIZE)
+                        .await?;
+                    Ok(trades)
+                }
+            },
+        )
+    }
+
+    /// Klines of `symbol` opened from `start_time` to `end_time`, or to now, fetched page by page,
+    /// see [`crate::backfill`]
+    pub fn backfill_klines<S1, S2, S3>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        start_time: u64,
+        end_time: S3,
+    ) -> impl Stream<Item = Result<KlineSummary>> + Send + 'static
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<Option<u64>>,
+    {
+        let market = self.clone();
+        let (symbol, interval, end_time) = (symbol.into(), interval.into(), end_time.into());
+        paginate(
+            start_time,
+            |kline: &KlineSummary| kline.open_time as u64 + 1,
+            end_time,
+            FUTURES_KLINES_PAGE_SIZE,
+            move |start_time| {
+                let (market, symbol, interval) = (market.clone(), symbol.clone(), interval.clone());
+                async move {
+                    seed_rate_limits(&market.client, FUTURES_EXCHANGE_INFO, None).await?;
+                    let KlineSummaries::AllKlineSummaries(klines) = market
+                        .get_klines(symbol, interval, FUTURES_KLINES_PAGE_SIZE, start_time, end_time)
+                        .await?;
+                    Ok(klines)
+                }
+            },
+        )
     }
 
     /// Returns up to 'limit' blvt klines for given symbol and interval ("1m", "5m", ...)
@@ -462,6 +558,127 @@ impl FuturesMarket {
         Ok(klines)
     }
 
+    /// Returns up to 'limit' mark price klines for given symbol and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/futures/en/#mark-price-kline-candlestick-data
+    pub async fn get_mark_price_klines<S1, S2, S3, S4, S5>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = HistoryQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            symbol: symbol.into(),
+            interval: Some(interval.into()),
+            from_id: None,
+            period: None,
+        };
+        self.klines("/fapi/v1/markPriceKlines", query).await
+    }
+
+    /// Returns up to 'limit' index price klines for given pair and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/futures/en/#index-price-kline-candlestick-data
+    pub async fn get_index_price_klines<S1, S2, S3, S4, S5>(
+        &self,
+        pair: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = IndexQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            pair: pair.into(),
+            interval: Some(interval.into()),
+        };
+        self.klines("/fapi/v1/indexPriceKlines", query).await
+    }
+
+    /// Returns up to 'limit' continuous contract klines for given pair, contract type
+    /// ("PERPETUAL", "CURRENT_QUARTER", "NEXT_QUARTER") and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/futures/en/#continuous-contract-kline-candlestick-data
+    pub async fn get_continuous_contract_klines<S1, S2, S3, S4, S5, S6>(
+        &self,
+        pair: S1,
+        contract_type: S2,
+        interval: S3,
+        limit: S4,
+        start_time: S5,
+        end_time: S6,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<String>,
+        S4: Into<u16>,
+        S5: Into<Option<u64>>,
+        S6: Into<Option<u64>>,
+    {
+        let query = ContinuousKlineQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            pair: pair.into(),
+            contract_type: contract_type.into(),
+            interval: interval.into(),
+        };
+        self.klines("/fapi/v1/continuousKlines", query).await
+    }
+
+    /// Returns up to 'limit' premium index klines for given symbol and interval ("1m", "5m", ...)
+    /// https://binance-docs.github.io/apidocs/futures/en/#premium-index-kline-data
+    pub async fn get_premium_index_klines<S1, S2, S3, S4, S5>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        limit: S3,
+        start_time: S4,
+        end_time: S5,
+    ) -> Result<KlineSummaries>
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<u16>,
+        S4: Into<Option<u64>>,
+        S5: Into<Option<u64>>,
+    {
+        let query = HistoryQuery {
+            start_time: start_time.into(),
+            end_time: end_time.into(),
+            limit: limit.into(),
+            symbol: symbol.into(),
+            interval: Some(interval.into()),
+            from_id: None,
+            period: None,
+        };
+        self.klines("/fapi/v1/premiumIndexKlines", query).await
+    }
+
+    async fn klines<Q: serde::Serialize>(&self, endpoint: &str, query: Q) -> Result<KlineSummaries> {
+        let rows: Vec<Vec<Value>> = self.client.get_d(endpoint, Some(query)).await?;
+        KlineSummaries::try_from(rows)
+    }
+
     /// https://binance-docs.github.io/apidocs/futures/en/#notional-and-leverage-brackets-user_data
     pub async fn get_notional_leverage_brackets<S>(&self, symbol: S) -> Result<Vec<SymbolBrackets>>
     where
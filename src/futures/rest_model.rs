@@ -69,6 +69,19 @@ pub enum ContractType {
     Empty,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContractStatus {
+    PendingTrading,
+    Trading,
+    PreDelivering,
+    Delivering,
+    Delivered,
+    PreSettle,
+    Settling,
+    Close,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum OrderType {
@@ -105,7 +118,10 @@ pub enum WorkingType {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum MarginType {
+    #[serde(alias = "ISOLATED")]
     Isolated,
+    /// Margin calls spell it `CROSSED`
+    #[serde(alias = "CROSSED")]
     Cross,
 }
 
@@ -340,8 +356,13 @@ pub struct Order {
     pub activate_price: f64,
     #[serde(with = "string_or_float", default = "default_price_rate")]
     pub price_rate: f64,
+    /// Creation time, missing from order updates
+    #[serde(default)]
+    pub time: u64,
     pub update_time: u64,
     pub working_type: WorkingType,
+    /// Missing from liquidation orders
+    #[serde(default)]
     pub price_protect: bool,
 }
 
@@ -383,6 +404,163 @@ pub struct Transaction {
     price_protect: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderAmendment {
+    pub amendment_id: u64,
+    pub symbol: String,
+    pub pair: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub time: u64,
+    pub amendment: Amendment,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Amendment {
+    pub price: AmendedValue,
+    pub orig_qty: AmendedValue,
+    /// Number of amendments of the order so far
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AmendedValue {
+    #[serde(with = "string_or_float")]
+    pub before: f64,
+    #[serde(with = "string_or_float")]
+    pub after: f64,
+}
+
+/// A trade of the account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTrade {
+    pub buyer: bool,
+    #[serde(with = "string_or_float")]
+    pub commission: f64,
+    pub commission_asset: String,
+    pub id: u64,
+    pub maker: bool,
+    pub order_id: u64,
+    #[serde(with = "string_or_float")]
+    pub price: f64,
+    #[serde(with = "string_or_float")]
+    pub qty: f64,
+    #[serde(with = "string_or_float")]
+    pub quote_qty: f64,
+    #[serde(with = "string_or_float")]
+    pub realized_pnl: f64,
+    pub side: OrderSide,
+    pub position_side: PositionSide,
+    pub symbol: String,
+    pub time: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IncomeType {
+    Transfer,
+    WelcomeBonus,
+    RealizedPnl,
+    FundingFee,
+    Commission,
+    InsuranceClear,
+    ReferralKickback,
+    CommissionRebate,
+    ApiRebate,
+    ContestReward,
+    CrossCollateralTransfer,
+    OptionsPremiumFee,
+    OptionsSettleProfit,
+    InternalTransfer,
+    AutoExchange,
+    #[serde(rename = "DELIVERED_SETTELMENT")]
+    DeliveredSettlement,
+    CoinSwapDeposit,
+    CoinSwapWithdraw,
+    PositionLimitIncreaseFee,
+    StrategyUmfuturesTransfer,
+    FeeReturn,
+    BfusdReward,
+    /// Income types added after this version
+    #[serde(other)]
+    Other,
+}
+
+/// A change of the balance of an asset of the account
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Income {
+    /// Empty for incomes of no symbol, such as transfers
+    pub symbol: String,
+    pub income_type: IncomeType,
+    #[serde(with = "string_or_float")]
+    pub income: f64,
+    pub asset: String,
+    pub info: String,
+    pub time: u64,
+    pub tran_id: u64,
+    /// Empty for incomes of no trade
+    pub trade_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionRate {
+    pub symbol: String,
+    #[serde(with = "string_or_float")]
+    pub maker_commission_rate: f64,
+    #[serde(with = "string_or_float")]
+    pub taker_commission_rate: f64,
+}
+
+/// The auto-deleveraging queue position of the positions of a symbol, from 0 to 4, 4 being the
+/// first to be deleveraged
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AdlQuantile {
+    pub symbol: String,
+    pub adl_quantile: AdlQuantiles,
+}
+
+/// `long` and `short` in hedge mode, `both` in one-way mode
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct AdlQuantiles {
+    pub long: Option<u8>,
+    pub short: Option<u8>,
+    pub both: Option<u8>,
+    /// Set to 0 in hedge mode, the quantiles being the ones of `long` and `short`
+    pub hedge: Option<u8>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AutoCloseType {
+    Liquidation,
+    Adl,
+}
+
+/// The result of an order of a batch, which fails or succeeds independently of the others
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BatchResult<T> {
+    Success(T),
+    Failure(crate::errors::BinanceContentError),
+}
+
+impl<T> BatchResult<T> {
+    /// The order, or its error mapped like the error of a single order request
+    pub fn into_result(self) -> crate::errors::Result<T> {
+        match self {
+            BatchResult::Success(order) => Ok(order),
+            BatchResult::Failure(error) => Err(crate::client::handle_content_error(error)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CanceledOrder {
@@ -615,6 +793,17 @@ pub(crate) struct IndexQuery {
     pub interval: Option<String>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContinuousKlineQuery {
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    pub limit: u16,
+    pub pair: String,
+    pub contract_type: String,
+    pub interval: String,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct FundingRate {
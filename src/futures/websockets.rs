@@ -1,16 +1,14 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use futures::StreamExt;
-use serde_json::from_str;
 use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::handshake::client::Response;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::MaybeTlsStream;
 use tokio_tungstenite::WebSocketStream;
-use tokio_tungstenite::{connect_async, MaybeTlsStream};
-use url::Url;
 
 use crate::config::Config;
 use crate::errors::*;
+use crate::websockets::{parse_event, ConnectionEvent, ConnectionHandler, SocketHandler, WebSocketSender};
 
 pub static STREAM_ENDPOINT: &str = "stream";
 pub static WS_ENDPOINT: &str = "ws";
@@ -56,12 +54,64 @@ pub fn partial_book_depth_stream(symbol: &str, levels: u16, update_speed: u16) -
 /// * `update_speed`: 1000 or 100
 pub fn diff_book_depth_stream(symbol: &str, update_speed: u16) -> String { format!("{symbol}@depth@{update_speed}ms") }
 
-fn combined_stream(streams: Vec<String>) -> String { streams.join("/") }
+/// # Arguments
+///
+/// * `symbol`: the market symbol
+/// * `update_speed`: 1 or 3 seconds
+pub fn mark_price_stream(symbol: &str, update_speed: u8) -> String {
+    match update_speed {
+        1 => format!("{symbol}@markPrice@1s"),
+        _ => format!("{symbol}@markPrice"),
+    }
+}
+
+/// Mark prices of every symbol, sent as an array of events
+///
+/// # Arguments
+///
+/// * `update_speed`: 1 or 3 seconds
+pub fn all_mark_price_stream(update_speed: u8) -> &'static str {
+    match update_speed {
+        1 => "!markPrice@arr@1s",
+        _ => "!markPrice@arr",
+    }
+}
+
+/// Liquidation orders of a symbol
+pub fn liquidation_stream(symbol: &str) -> String { format!("{symbol}@forceOrder") }
+
+/// Liquidation orders of every symbol, each sent as a single event
+pub fn all_liquidation_stream() -> &'static str { "!forceOrder@arr" }
+
+/// # Arguments
+///
+/// * `pair`: the underlying pair, such as `btcusdt`
+/// * `contract_type`: `perpetual`, `current_quarter` or `next_quarter`
+/// * `interval`: the kline interval, such as `1m`
+pub fn continuous_kline_stream(pair: &str, contract_type: &str, interval: &str) -> String {
+    format!("{pair}_{contract_type}@continuousKline_{interval}")
+}
+
+pub fn index_price_kline_stream(pair: &str, interval: &str) -> String { format!("{pair}@indexPriceKline_{interval}") }
+
+pub fn mark_price_kline_stream(symbol: &str, interval: &str) -> String { format!("{symbol}@markPriceKline_{interval}") }
+
+/// Composition of the index of a composite index symbol, such as `defiusdt`
+pub fn composite_index_stream(symbol: &str) -> String { format!("{symbol}@compositeIndex") }
+
+/// Listings, settlements and bracket changes of every contract
+pub fn contract_info_stream() -> &'static str { "!contractInfo" }
+
+/// Index prices of a multi-assets mode asset, such as `adausd`
+pub fn asset_index_stream(asset_symbol: &str) -> String { format!("{asset_symbol}@assetIndex") }
+
+/// Index prices of every multi-assets mode asset, sent as an array of events
+pub fn all_asset_index_stream() -> &'static str { "!assetIndex@arr" }
 
 pub struct WebSockets<'a, WE> {
-    pub socket: Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)>,
+    pub socket_handler: SocketHandler,
     handler: Box<dyn FnMut(WE) -> Result<()> + 'a + Send>,
-    conf: Config,
+    connection_handler: Option<ConnectionHandler<'a>>,
 }
 
 impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
@@ -83,73 +133,58 @@ impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
         Callback: FnMut(WE) -> Result<()> + 'a + Send,
     {
         WebSockets {
-            socket: None,
+            socket_handler: SocketHandler::new_with_options(conf),
             handler: Box::new(handler),
-            conf,
+            connection_handler: None,
         }
     }
 
+    /// Sets the handler notified when the websocket disconnects and reconnects, see
+    /// [`crate::config::Config::set_reconnect_policy`]
+    pub fn set_connection_handler<Callback>(mut self, handler: Callback) -> Self
+    where
+        Callback: FnMut(ConnectionEvent) -> Result<()> + 'a + Send,
+    {
+        self.connection_handler = Some(Box::new(handler));
+        self
+    }
+
     /// Connect to multiple websocket endpoints
     /// N.B: WE has to be CombinedStreamEvent
     pub async fn connect_multiple(&mut self, endpoints: Vec<String>) -> Result<()> {
-        let mut url = Url::parse(&self.conf.ws_endpoint)?;
-        url.path_segments_mut()
-            .map_err(|_| Error::UrlParserError(url::ParseError::RelativeUrlWithoutBase))?
-            .push(STREAM_ENDPOINT);
-        url.set_query(Some(&format!("streams={}", combined_stream(endpoints))));
-
-        self.handle_connect(url).await
+        self.socket_handler.connect_multiple_futures(endpoints).await
     }
 
     /// Connect to a websocket endpoint
     pub async fn connect(&mut self, endpoint: &str) -> Result<()> {
-        let wss: String = format!("{}/{}/{}", self.conf.futures_ws_endpoint, WS_ENDPOINT, endpoint);
-        let url = Url::parse(&wss)?;
-
-        self.handle_connect(url).await
+        self.socket_handler.connect_futures(endpoint).await
     }
 
-    async fn handle_connect(&mut self, url: Url) -> Result<()> {
-        match connect_async(url).await {
-            Ok(answer) => {
-                self.socket = Some(answer);
-                Ok(())
-            }
-            Err(e) => Err(Error::Msg(format!("Error during handshake {e}"))),
-        }
-    }
+    /// A sender to subscribe to streams or unsubscribe from them while the event loop runs
+    pub fn sender(&mut self) -> WebSocketSender { self.socket_handler.sender() }
 
     /// Disconnect from the endpoint
-    pub async fn disconnect(&mut self) -> Result<()> {
-        if let Some(ref mut socket) = self.socket {
-            socket.0.close(None).await?;
-            Ok(())
-        } else {
-            Err(Error::Msg("Not able to close the connection".to_string()))
-        }
-    }
+    pub async fn disconnect(&mut self) -> Result<()> { self.socket_handler.disconnect().await }
 
-    pub fn socket(&self) -> &Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)> { &self.socket }
+    pub fn socket(&self) -> &Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)> {
+        self.socket_handler.socket()
+    }
 
     pub async fn event_loop(&mut self, running: &AtomicBool) -> Result<()> {
         while running.load(Ordering::Relaxed) {
-            if let Some((ref mut socket, _)) = self.socket {
-                // TODO: return error instead of panic?
-                let message = socket.next().await.unwrap()?;
-
-                match message {
-                    Message::Text(msg) => {
-                        if msg.is_empty() {
-                            return Ok(());
-                        }
-                        let event: WE = from_str(msg.as_str())?;
-                        (self.handler)(event)?;
-                    }
-                    Message::Ping(_) | Message::Pong(_) | Message::Binary(_) | Message::Frame(_) => {}
-                    Message::Close(e) => {
-                        return Err(Error::Msg(format!("Disconnected {e:?}")));
-                    }
+            let Some(message) = self
+                .socket_handler
+                .next_message(running, &mut self.connection_handler)
+                .await?
+            else {
+                break;
+            };
+            if let Message::Text(msg) = message {
+                if msg.is_empty() {
+                    return Ok(());
                 }
+                let event: WE = parse_event(&self.socket_handler.stream, msg.as_str())?;
+                (self.handler)(event)?;
             }
         }
         Ok(())
@@ -1,14 +1,435 @@
-use crate::futures::rest_model::{MarginType, OrderType, PositionSide, WorkingType};
-use crate::rest_model::{string_or_float, string_or_float_opt, ExecutionType, OrderSide, OrderStatus, TimeInForce};
+use crate::futures::rest_model::{ContractStatus, ContractType, MarginType, OrderType, PositionSide, WorkingType};
+use crate::rest_model::{string_or_float, string_or_float_opt, Asks, Bids, ExecutionType, OrderSide, OrderStatus,
+                        TimeInForce};
 use crate::ws_model::MarkPriceEvent;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
-#[serde(rename_all = "SCREAMING_SNAKE_CASE", tag = "e")]
+#[serde(remote = "Self", rename_all = "SCREAMING_SNAKE_CASE", tag = "e")]
 pub enum WebsocketEvent {
     AccountUpdate(Box<AccountUpdate>),
     OrderTradeUpdate(Box<OrderTradeUpdate>),
     #[serde(alias = "markPriceUpdate")]
     MarkPriceUpdate(Box<MarkPriceEvent>),
+    #[serde(rename = "depthUpdate")]
+    DepthOrderBook(Box<DepthOrderBookEvent>),
+    #[serde(rename = "forceOrder")]
+    Liquidation(Box<LiquidationEvent>),
+    #[serde(rename = "continuous_kline")]
+    ContinuousKline(Box<ContinuousKlineEvent>),
+    #[serde(rename = "indexPrice_kline")]
+    IndexPriceKline(Box<PriceKlineEvent>),
+    #[serde(rename = "markPrice_kline")]
+    MarkPriceKline(Box<PriceKlineEvent>),
+    #[serde(rename = "compositeIndex")]
+    CompositeIndex(Box<CompositeIndexEvent>),
+    #[serde(rename = "contractInfo")]
+    ContractInfo(Box<ContractInfoEvent>),
+    #[serde(rename = "assetIndexUpdate")]
+    AssetIndex(Box<AssetIndexEvent>),
+    MarginCall(Box<MarginCallEvent>),
+    #[serde(rename = "listenKeyExpired")]
+    ListenKeyExpired(Box<ListenKeyExpiredEvent>),
+    AccountConfigUpdate(Box<AccountConfigUpdateEvent>),
+    StrategyUpdate(Box<StrategyUpdateEvent>),
+    GridUpdate(Box<GridUpdateEvent>),
+    ConditionalOrderTriggerReject(Box<ConditionalOrderTriggerRejectEvent>),
+    /// Mark prices of every symbol, see [`crate::futures::websockets::all_mark_price_stream`]
+    #[serde(skip)]
+    MarkPriceArr(Vec<MarkPriceEvent>),
+    /// Index prices of every multi-assets mode asset, see
+    /// [`crate::futures::websockets::all_asset_index_stream`]
+    #[serde(skip)]
+    AssetIndexArr(Vec<AssetIndexEvent>),
+    /// An event this crate does not know of yet
+    #[serde(other)]
+    Unknown,
+}
+
+/// Events are tagged by their `e` field, except the arrays of events of the `!markPrice@arr` and
+/// `!assetIndex@arr` streams
+impl<'de> Deserialize<'de> for WebsocketEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let event = match &value {
+            Value::Array(events) => match events.first().and_then(|event| event.get("e")).and_then(Value::as_str) {
+                Some("assetIndexUpdate") => serde_json::from_value(value).map(WebsocketEvent::AssetIndexArr),
+                _ => serde_json::from_value(value).map(WebsocketEvent::MarkPriceArr),
+            },
+            _ => WebsocketEvent::deserialize(value),
+        };
+        event.map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for WebsocketEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            WebsocketEvent::MarkPriceArr(events) => events.serialize(serializer),
+            WebsocketEvent::AssetIndexArr(events) => events.serialize(serializer),
+            event => WebsocketEvent::serialize(event, serializer),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "o")]
+    pub order: LiquidationOrder,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LiquidationOrder {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "S")]
+    pub side: OrderSide,
+    #[serde(rename = "o")]
+    pub order_type: OrderType,
+    #[serde(rename = "f")]
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "q", with = "string_or_float")]
+    pub original_quantity: f64,
+    #[serde(rename = "p", with = "string_or_float")]
+    pub price: f64,
+    #[serde(rename = "ap", with = "string_or_float")]
+    pub average_price: f64,
+    #[serde(rename = "X")]
+    pub order_status: OrderStatus,
+    #[serde(rename = "l", with = "string_or_float")]
+    pub last_filled_quantity: f64,
+    #[serde(rename = "z", with = "string_or_float")]
+    pub filled_accumulated_quantity: f64,
+    #[serde(rename = "T")]
+    pub trade_time: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContinuousKlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "ct")]
+    pub contract_type: ContractType,
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+/// Kline of the index or the mark price of a pair, its volumes and trade ids are always 0
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PriceKlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "k")]
+    pub kline: Kline,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Kline {
+    #[serde(rename = "t")]
+    pub start_time: i64,
+    #[serde(rename = "T")]
+    pub end_time: i64,
+    #[serde(rename = "i")]
+    pub interval: String,
+    #[serde(rename = "f")]
+    pub first_trade_id: i64,
+    #[serde(rename = "L")]
+    pub last_trade_id: i64,
+    #[serde(rename = "o", with = "string_or_float")]
+    pub open: f64,
+    #[serde(rename = "c", with = "string_or_float")]
+    pub close: f64,
+    #[serde(rename = "h", with = "string_or_float")]
+    pub high: f64,
+    #[serde(rename = "l", with = "string_or_float")]
+    pub low: f64,
+    #[serde(rename = "v", with = "string_or_float")]
+    pub volume: f64,
+    #[serde(rename = "n")]
+    pub number_of_trades: i64,
+    #[serde(rename = "x")]
+    pub is_final_bar: bool,
+    #[serde(rename = "q", with = "string_or_float")]
+    pub quote_volume: f64,
+    #[serde(rename = "V", with = "string_or_float")]
+    pub taker_buy_volume: f64,
+    #[serde(rename = "Q", with = "string_or_float")]
+    pub taker_buy_quote_volume: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CompositeIndexEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p", with = "string_or_float")]
+    pub price: f64,
+    /// The asset the weights are expressed in, `baseAsset` or `quoteAsset`
+    #[serde(rename = "C")]
+    pub weight_asset: String,
+    #[serde(rename = "c")]
+    pub composition: Vec<IndexComponent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndexComponent {
+    #[serde(rename = "b")]
+    pub base_asset: String,
+    #[serde(rename = "q")]
+    pub quote_asset: String,
+    #[serde(rename = "w", with = "string_or_float")]
+    pub weight_in_quantity: f64,
+    #[serde(rename = "W", with = "string_or_float")]
+    pub weight_in_percentage: f64,
+    #[serde(rename = "i", with = "string_or_float")]
+    pub index_price: f64,
+}
+
+/// Sent when a contract is listed, settled or delisted, or when its notional brackets change
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContractInfoEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub pair: String,
+    #[serde(rename = "ct")]
+    pub contract_type: ContractType,
+    #[serde(rename = "dt")]
+    pub delivery_date: u64,
+    #[serde(rename = "ot")]
+    pub onboard_date: u64,
+    #[serde(rename = "cs")]
+    pub contract_status: ContractStatus,
+    /// Only sent when the brackets change
+    #[serde(default, rename = "bks", skip_serializing_if = "Option::is_none")]
+    pub brackets: Option<Vec<NotionalBracket>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotionalBracket {
+    #[serde(rename = "bs")]
+    pub bracket: u32,
+    #[serde(rename = "bnf")]
+    pub notional_floor: f64,
+    #[serde(rename = "bnc")]
+    pub notional_cap: f64,
+    #[serde(rename = "mmr")]
+    pub maintenance_ratio: f64,
+    #[serde(rename = "cf")]
+    pub cumulative: f64,
+    #[serde(rename = "mi")]
+    pub min_leverage: u32,
+    #[serde(rename = "ma")]
+    pub max_leverage: u32,
+}
+
+/// Index price of a multi-assets mode margin asset, with the buffers and rates applied to it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AssetIndexEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "i", with = "string_or_float")]
+    pub index_price: f64,
+    #[serde(rename = "b", with = "string_or_float")]
+    pub bid_buffer: f64,
+    #[serde(rename = "a", with = "string_or_float")]
+    pub ask_buffer: f64,
+    #[serde(rename = "B", with = "string_or_float")]
+    pub bid_rate: f64,
+    #[serde(rename = "A", with = "string_or_float")]
+    pub ask_rate: f64,
+    #[serde(rename = "q", with = "string_or_float")]
+    pub auto_exchange_bid_buffer: f64,
+    #[serde(rename = "g", with = "string_or_float")]
+    pub auto_exchange_ask_buffer: f64,
+    #[serde(rename = "Q", with = "string_or_float")]
+    pub auto_exchange_bid_rate: f64,
+    #[serde(rename = "G", with = "string_or_float")]
+    pub auto_exchange_ask_rate: f64,
+}
+
+/// Diff depth event, unlike spot it carries the final update id of the previous event
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DepthOrderBookEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "pu")]
+    pub previous_final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<Bids>,
+    #[serde(rename = "a")]
+    pub asks: Vec<Asks>,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct MarginCallEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    /// Only sent for cross margin calls
+    #[serde(default, rename = "cw", with = "string_or_float_opt")]
+    pub cross_wallet_balance: Option<f64>,
+    #[serde(rename = "p")]
+    pub positions: Vec<MarginCallPosition>,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct MarginCallPosition {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ps")]
+    pub position_side: PositionSide,
+    #[serde(rename = "pa", with = "string_or_float")]
+    pub position_amount: f64,
+    #[serde(rename = "mt")]
+    pub margin_type: MarginType,
+    #[serde(rename = "iw", with = "string_or_float")]
+    pub isolated_wallet: f64,
+    #[serde(rename = "mp", with = "string_or_float")]
+    pub mark_price: f64,
+    #[serde(rename = "up", with = "string_or_float")]
+    pub unrealized_profit: f64,
+    #[serde(rename = "mm", with = "string_or_float")]
+    pub maintenance_margin_required: f64,
+}
+
+/// The listen key expired, the user data stream has to be started again
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct ListenKeyExpiredEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "listenKey")]
+    pub listen_key: String,
+}
+
+/// A change of the leverage of a symbol or of the multi-assets mode
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct AccountConfigUpdateEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(default, rename = "ac", skip_serializing_if = "Option::is_none")]
+    pub leverage: Option<LeverageUpdate>,
+    #[serde(default, rename = "ai", skip_serializing_if = "Option::is_none")]
+    pub multi_assets: Option<MultiAssetsUpdate>,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct LeverageUpdate {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "l")]
+    pub leverage: u8,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct MultiAssetsUpdate {
+    #[serde(rename = "j")]
+    pub multi_assets_margin: bool,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct StrategyUpdateEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "su")]
+    pub strategy: StrategyUpdate,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct StrategyUpdate {
+    #[serde(rename = "si")]
+    pub strategy_id: u64,
+    /// Such as `GRID`
+    #[serde(rename = "st")]
+    pub strategy_type: String,
+    /// `NEW`, `WORKING`, `CANCELLED` or `EXPIRED`
+    #[serde(rename = "ss")]
+    pub strategy_status: String,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "ut")]
+    pub update_time: u64,
+    /// Why the strategy changed, see the Binance documentation of the strategy update codes
+    #[serde(rename = "c")]
+    pub op_code: u32,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct GridUpdateEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "gu")]
+    pub grid: GridUpdate,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct GridUpdate {
+    #[serde(rename = "si")]
+    pub strategy_id: u64,
+    #[serde(rename = "st")]
+    pub strategy_type: String,
+    #[serde(rename = "ss")]
+    pub strategy_status: String,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "r", with = "string_or_float")]
+    pub realized_profit: f64,
+    #[serde(rename = "up", with = "string_or_float")]
+    pub unmatched_average_price: f64,
+    #[serde(rename = "uq", with = "string_or_float")]
+    pub unmatched_qty: f64,
+    #[serde(rename = "uf", with = "string_or_float")]
+    pub unmatched_fee: f64,
+    #[serde(rename = "mp", with = "string_or_float")]
+    pub matched_profit: f64,
+    #[serde(rename = "ut")]
+    pub update_time: u64,
+}
+
+/// A triggered conditional order was rejected
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct ConditionalOrderTriggerRejectEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "or")]
+    pub order: RejectedOrder,
+}
+
+#[derive(Clone, Serialize, Debug, Deserialize)]
+pub struct RejectedOrder {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "r")]
+    pub reason: String,
 }
 
 #[derive(Clone, Serialize, Debug, Deserialize)]
@@ -177,18 +598,24 @@ pub enum PriceMatch {
     /// Counterparty best price
     Opponent,
     /// The 5th best price from the counterparty
+    #[serde(rename = "OPPONENT_5")]
     Opponent5,
     /// The 10th best price from the counterparty
+    #[serde(rename = "OPPONENT_10")]
     Opponent10,
     /// The 20th best price from the counterparty
+    #[serde(rename = "OPPONENT_20")]
     Opponent20,
     /// The best price on the same side of the order book
     Queue,
     /// The 5th best price on the same side of the order book
+    #[serde(rename = "QUEUE_5")]
     Queue5,
     /// The 10th best price on the same side of the order book
+    #[serde(rename = "QUEUE_10")]
     Queue10,
     /// The 20th best price on the same side of the order book
+    #[serde(rename = "QUEUE_20")]
     Queue20,
 }
 
@@ -204,3 +631,67 @@ pub enum SelfTradePreventionMode {
     /// Expire maker order when STP trigger
     ExpireMaker,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::WebsocketEvent;
+
+    #[test]
+    fn test_deserialize_market_streams() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("test_data/futures_market_stream.json");
+        let fc = std::fs::read_to_string(d).unwrap();
+
+        let events: Vec<WebsocketEvent> = serde_json::from_str(&fc).unwrap();
+        assert_eq!(events.len(), 9);
+        assert!(matches!(&events[1], WebsocketEvent::Liquidation(e) if e.order.price == 9910.0));
+        assert!(matches!(&events[3], WebsocketEvent::IndexPriceKline(e) if e.pair == "BTCUSDT"));
+        assert!(matches!(&events[4], WebsocketEvent::MarkPriceKline(_)));
+        assert!(matches!(&events[8], WebsocketEvent::DepthOrderBook(e) if e.previous_final_update_id == 149));
+    }
+
+    #[test]
+    fn test_deserialize_array_streams() {
+        let read = |name: &str| {
+            let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            d.push("test_data");
+            d.push(name);
+            std::fs::read_to_string(d).unwrap()
+        };
+
+        let event: WebsocketEvent = serde_json::from_str(&read("futures_mark_price_arr.json")).unwrap();
+        assert!(matches!(&event, WebsocketEvent::MarkPriceArr(e) if e.len() == 2 && e[1].symbol == "ETHUSDT"));
+
+        let event: WebsocketEvent = serde_json::from_str(&read("futures_asset_index_arr.json")).unwrap();
+        assert!(matches!(&event, WebsocketEvent::AssetIndexArr(e) if e.len() == 2 && e[0].index_price == 0.27462452));
+        assert!(serde_json::to_value(&event).unwrap().is_array());
+        let unknown: WebsocketEvent = serde_json::from_str(r#"{"e":"newEvent","E":1686749230000}"#).unwrap();
+        assert!(matches!(unknown, WebsocketEvent::Unknown));
+    }
+
+    #[test]
+    fn test_deserialize_userstream() {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("test_data/futures_userstream.json");
+        let fc = std::fs::read_to_string(d).unwrap();
+
+        let events: Vec<WebsocketEvent> = serde_json::from_str(&fc).unwrap();
+        assert_eq!(events.len(), 10);
+        assert!(matches!(&events[2], WebsocketEvent::MarginCall(e) if e.cross_wallet_balance == Some(3.16812045)));
+        assert!(matches!(&events[3], WebsocketEvent::ListenKeyExpired(_)));
+        assert!(
+            matches!(&events[4], WebsocketEvent::AccountConfigUpdate(e) if e.leverage.as_ref().unwrap().leverage == 25)
+        );
+        assert!(
+            matches!(&events[5], WebsocketEvent::AccountConfigUpdate(e) if e.multi_assets.as_ref().unwrap().multi_assets_margin)
+        );
+        assert!(matches!(&events[6], WebsocketEvent::StrategyUpdate(e) if e.strategy.op_code == 8007));
+        assert!(matches!(&events[7], WebsocketEvent::GridUpdate(e) if e.grid.unmatched_qty == -0.001));
+        assert!(
+            matches!(&events[8], WebsocketEvent::ConditionalOrderTriggerReject(e) if e.order.order_id == 155618472834)
+        );
+        assert!(matches!(&events[9], WebsocketEvent::Unknown));
+    }
+}
@@ -1,8 +1,13 @@
 use serde_json::Value;
 
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+
 use crate::client::*;
 use crate::errors::*;
 use crate::rest_model::*;
+use crate::time_sync::TimeOffset;
 
 #[derive(Clone)]
 pub struct General {
@@ -37,7 +42,23 @@ impl General {
     /// ```
     pub async fn get_server_time(&self) -> Result<ServerTime> { self.client.get("/api/v3/time", None).await }
 
+    /// Measure the offset between the local clock and the server time, used to correct the
+    /// timestamp of signed requests made by every client sharing this client's config
+    pub async fn sync_server_time(&self) -> Result<TimeOffset> {
+        self.client.time_sync().sync(|| self.get_server_time()).await
+    }
+
+    /// Keep the server time offset up to date in a background task, see [`crate::time_sync::TimeSync::spawn`]
+    pub fn spawn_time_sync(&self, interval: Duration) -> JoinHandle<()> {
+        let general = self.clone();
+        self.client.time_sync().spawn(interval, move || {
+            let general = general.clone();
+            async move { general.get_server_time().await }
+        })
+    }
+
     /// Obtain exchange information (rate limits, symbol metadata etc)
+    /// The rate limits are used to seed the client's rate limiter
     /// # Examples
     /// ```rust
     /// use binance::{api::*, general::*, config::*};
@@ -47,6 +68,8 @@ impl General {
     /// assert!(exchange_info.is_ok(), "{:?}", exchange_info);
     /// ```
     pub async fn exchange_info(&self) -> Result<ExchangeInformation> {
-        self.client.get("/api/v3/exchangeInfo", None).await
+        let info: ExchangeInformation = self.client.get("/api/v3/exchangeInfo", None).await?;
+        self.client.set_rate_limits(&info.rate_limits);
+        Ok(info)
     }
 }
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::errors::*;
+
+/// HTTP versions the pooled connections may use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HttpVersion {
+    /// HTTP/2 when negotiated with the server, HTTP/1.1 otherwise
+    #[default]
+    Auto,
+    Http1Only,
+    /// Use HTTP/2 without negotiation
+    Http2PriorKnowledge,
+}
+
+/// Settings of the connection pool, unset values keep the defaults of `reqwest`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HttpOptions {
+    /// Maximum idle connections kept per host
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long idle connections are kept
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keep-alive interval of the connections
+    pub tcp_keepalive: Option<Duration>,
+    pub http_version: HttpVersion,
+    /// Interval of the HTTP/2 pings keeping connections alive
+    pub http2_keep_alive_interval: Option<Duration>,
+    /// Proxy url used for every request, e.g. `http://proxy:3128` or `socks5://proxy:1080`
+    pub proxy: Option<String>,
+    /// Local address to bind the connections to
+    pub local_address: Option<IpAddr>,
+}
+
+/// A `reqwest::Client` shared by every client created from the same [`crate::config::Config`],
+/// so that all API structs reuse one connection pool and its TLS sessions.
+///
+/// The client of the default options is built on first use, the one of other options when the
+/// pool is created. Cloning an [`HttpPool`] shares the client.
+#[derive(Clone, Default)]
+pub struct HttpPool {
+    options: HttpOptions,
+    client: Arc<OnceLock<std::result::Result<reqwest::Client, String>>>,
+}
+
+impl HttpPool {
+    /// A pool of `options`, failing if the proxy url is invalid or the TLS backend cannot be
+    /// initialized
+    pub fn new(options: HttpOptions) -> Result<Self> {
+        let client = build_client(&options)?;
+        Ok(Self {
+            options,
+            client: Arc::new(OnceLock::from(Ok(client))),
+        })
+    }
+
+    pub fn options(&self) -> &HttpOptions { &self.options }
+
+    /// The pooled client, failing if it cannot be built with the default options
+    pub fn client(&self) -> Result<reqwest::Client> {
+        self.client
+            .get_or_init(|| build_client(&self.options).map_err(|e| e.to_string()))
+            .clone()
+            .map_err(Error::HttpClientUnavailable)
+    }
+}
+
+fn build_client(options: &HttpOptions) -> reqwest::Result<reqwest::Client> {
+    let mut builder = reqwest::ClientBuilder::new();
+    if let Some(max) = options.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max);
+    }
+    if let Some(idle_timeout) = options.pool_idle_timeout {
+        builder = builder.pool_idle_timeout(idle_timeout);
+    }
+    if let Some(keepalive) = options.tcp_keepalive {
+        builder = builder.tcp_keepalive(keepalive);
+    }
+    builder = match options.http_version {
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1Only => builder.http1_only(),
+        HttpVersion::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+    };
+    if let Some(interval) = options.http2_keep_alive_interval {
+        builder = builder
+            .http2_keep_alive_interval(interval)
+            .http2_keep_alive_while_idle(true);
+    }
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    if let Some(address) = options.local_address {
+        builder = builder.local_address(address);
+    }
+    builder.build()
+}
+
+impl fmt::Debug for HttpPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpPool").field("options", &self.options).finish()
+    }
+}
+
+/// Two pools are equal when they share the same client
+impl PartialEq for HttpPool {
+    fn eq(&self, other: &Self) -> bool { self.options == other.options && Arc::ptr_eq(&self.client, &other.client) }
+}
+
+impl Eq for HttpPool {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client() {
+        let options = HttpOptions {
+            pool_max_idle_per_host: Some(4),
+            pool_idle_timeout: Some(Duration::from_secs(30)),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http_version: HttpVersion::Http1Only,
+            http2_keep_alive_interval: Some(Duration::from_secs(20)),
+            proxy: Some("http://localhost:3128".to_string()),
+            local_address: Some("127.0.0.1".parse().unwrap()),
+        };
+        assert!(HttpPool::new(options).is_ok());
+
+        let options = HttpOptions {
+            proxy: Some("not a url".to_string()),
+            ..HttpOptions::default()
+        };
+        assert!(HttpPool::new(options).is_err());
+    }
+
+    #[test]
+    fn test_pool_is_shared_by_clones() {
+        let pool = HttpPool::default();
+        let shared = pool.clone();
+        shared.client().unwrap();
+        assert!(pool.client.get().is_some());
+        assert_eq!(pool, shared);
+        assert_ne!(pool, HttpPool::default());
+    }
+
+    #[test]
+    fn test_timeout_keeps_pool_shared() {
+        let config = crate::config::Config::default();
+        let pool = config.http_pool.clone();
+        assert_eq!(config.set_timeout(3).http_pool, pool);
+    }
+}
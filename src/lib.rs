@@ -77,19 +77,36 @@ pub mod util;
 
 pub mod account;
 pub mod api;
+pub mod backfill;
 pub mod coin_margin;
 pub mod config;
+pub mod credentials;
+pub mod error_codes;
 #[cfg(feature = "futures_api")]
 pub mod futures;
 pub mod general;
+pub mod http_pool;
 #[cfg(feature = "margin_api")]
 pub mod margin;
 pub mod market;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod middleware;
+pub mod order_book;
+pub mod rate_limit;
 pub mod rest_model;
 #[cfg(feature = "savings_api")]
 pub mod savings;
+pub mod signer;
+pub mod time_sync;
+pub mod user_data;
 pub mod userstream;
 #[cfg(feature = "wallet_api")]
 pub mod wallet;
 pub mod websockets;
+pub mod ws_api;
 pub mod ws_model;
+pub mod ws_multiplexer;
+
+#[cfg(test)]
+pub(crate) mod test_util;
@@ -1,7 +1,10 @@
+use crate::backfill::{paginate, seed_rate_limits, Cursor, SPOT_AGG_TRADES_PAGE_SIZE, SPOT_EXCHANGE_INFO,
+                      SPOT_KLINES_PAGE_SIZE};
 use crate::client::*;
 use crate::errors::*;
 use crate::rest_model::*;
 use crate::util::*;
+use futures::Stream;
 use serde_json::Value;
 // use std::collections::BTreeMap;
 
@@ -197,6 +200,54 @@ impl Market {
         self.client.get_p(API_V3_AGG_TRADES, Some(&request)).await
     }
 
+    /// Aggregated trades of `symbol` from `start_time` to `end_time`, or to now, fetched page by
+    /// page, see [`crate::backfill`]
+    /// # Examples
+    /// ```rust,no_run
+    /// use binance::{api::*, market::*, config::*};
+    /// use futures::TryStreamExt;
+    /// let market: Market = Binance::new_with_env(&Config::default());
+    /// let trades = market.backfill_agg_trades("BTCUSDT", 1_700_000_000_000, 1_700_003_600_000);
+    /// let trades: Vec<_> = tokio_test::block_on(trades.try_collect()).unwrap();
+    /// ```
+    pub fn backfill_agg_trades<S1, S2>(
+        &self,
+        symbol: S1,
+        start_time: u64,
+        end_time: S2,
+    ) -> impl Stream<Item = Result<AggTrade>> + Send + 'static
+    where
+        S1: Into<String>,
+        S2: Into<Option<u64>>,
+    {
+        let market = self.clone();
+        let symbol = symbol.into();
+        paginate(
+            Cursor::StartTime(start_time),
+            |trade: &AggTrade| Cursor::FromId(trade.agg_id + 1),
+            end_time.into(),
+            SPOT_AGG_TRADES_PAGE_SIZE,
+            move |cursor| {
+                let (market, symbol) = (market.clone(), symbol.clone());
+                async move {
+                    seed_rate_limits(
+                        &market.client,
+                        SPOT_EXCHANGE_INFO,
+                        Some(&market.symbol_request(&symbol)),
+                    )
+                    .await?;
+                    let (from_id, start_time) = match cursor {
+                        Cursor::StartTime(start_time) => (None, Some(start_time)),
+                        Cursor::FromId(from_id) => (Some(from_id), None),
+                    };
+                    market
+                        .get_agg_trades(symbol, from_id, start_time, None, SPOT_AGG_TRADES_PAGE_SIZE)
+                        .await
+                }
+            },
+        )
+    }
+
     /// Returns up to 'limit' klines for given symbol and interval ("1m", "5m", ...)
     /// <https://github.com/binance-exchange/binance-official-api-docs/blob/master/rest-api.md#klinecandlestick-data>
     /// # Examples
@@ -233,26 +284,54 @@ impl Market {
 
         let request = build_request(parameters);
 
-        let parsed_data: Vec<Vec<Value>> = self.client.get(API_V3_KLINES, Some(&request)).await?;
-        unimplemented!("TODO: Parse klines");
-        // let klines = KlineSummaries::AllKlineSummaries(
-        //     parsed_data
-        //         .iter()
-        //         .map(|row| KlineSummary {
-        //             open_time: to_i64(&row[0]),
-        //             open: to_f64(&row[1]),
-        //             high: to_f64(&row[2]),
-        //             low: to_f64(&row[3]),
-        //             close: to_f64(&row[4]),
-        //             volume: to_f64(&row[5]),
-        //             close_time: to_i64(&row[6]),
-        //             quote_asset_volume: to_f64(&row[7]),
-        //             number_of_trades: to_i64(&row[8]),
-        //             taker_buy_base_asset_volume: to_f64(&row[9]),
-        //             taker_buy_quote_asset_volume: to_f64(&row[10]),
-        //         })
-        //         .collect(),
-        // );
-        // Ok(klines)
+        let rows: Vec<Vec<Value>> = self.client.get(API_V3_KLINES, Some(&request)).await?;
+        KlineSummaries::try_from(rows)
+    }
+
+    /// Klines of `symbol` opened from `start_time` to `end_time`, or to now, fetched page by page,
+    /// see [`crate::backfill`]
+    /// # Examples
+    /// ```rust,no_run
+    /// use binance::{api::*, market::*, config::*};
+    /// use futures::TryStreamExt;
+    /// let market: Market = Binance::new_with_env(&Config::default());
+    /// let klines = market.backfill_klines("BTCUSDT", "1m", 1_600_000_000_000, None);
+    /// let klines: Vec<_> = tokio_test::block_on(klines.try_collect()).unwrap();
+    /// ```
+    pub fn backfill_klines<S1, S2, S3>(
+        &self,
+        symbol: S1,
+        interval: S2,
+        start_time: u64,
+        end_time: S3,
+    ) -> impl Stream<Item = Result<KlineSummary>> + Send + 'static
+    where
+        S1: Into<String>,
+        S2: Into<String>,
+        S3: Into<Option<u64>>,
+    {
+        let market = self.clone();
+        let (symbol, interval, end_time) = (symbol.into(), interval.into(), end_time.into());
+        paginate(
+            start_time,
+            |kline: &KlineSummary| kline.open_time as u64 + 1,
+            end_time,
+            SPOT_KLINES_PAGE_SIZE,
+            move |start_time| {
+                let (market, symbol, interval) = (market.clone(), symbol.clone(), interval.clone());
+                async move {
+                    seed_rate_limits(
+                        &market.client,
+                        SPOT_EXCHANGE_INFO,
+                        Some(&market.symbol_request(&symbol)),
+                    )
+                    .await?;
+                    let KlineSummaries::AllKlineSummaries(klines) = market
+                        .get_klines(symbol, interval, SPOT_KLINES_PAGE_SIZE, start_time, end_time)
+                        .await?;
+                    Ok(klines)
+                }
+            },
+        )
     }
 }
@@ -0,0 +1,137 @@
+//! Prometheus metrics of the REST clients and websockets, enabled by the `metrics` feature.
+//!
+//! Metrics are recorded process wide and exposed through [`register`], which adds them to an
+//! existing registry.
+//!
+//! # Examples
+//!
+//! ```
+//! let registry = prometheus::Registry::new();
+//! binance::metrics::register(&registry).unwrap();
+//! ```
+
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+
+use crate::errors::Error;
+
+lazy_static! {
+    static ref REQUESTS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "binance_requests_total",
+            "REST requests by endpoint, method and HTTP status"
+        ),
+        &["endpoint", "method", "status"]
+    )
+    .unwrap();
+    static ref REQUEST_DURATION: HistogramVec = HistogramVec::new(
+        HistogramOpts::new(
+            "binance_request_duration_seconds",
+            "Latency of REST requests by endpoint"
+        ),
+        &["endpoint"]
+    )
+    .unwrap();
+    static ref ERROR_CODES: IntCounterVec = IntCounterVec::new(
+        Opts::new("binance_error_codes_total", "Error codes returned by the exchange"),
+        &["endpoint", "code"]
+    )
+    .unwrap();
+    static ref WS_MESSAGES: IntCounterVec = IntCounterVec::new(
+        Opts::new("binance_ws_messages_total", "Websocket messages received by stream"),
+        &["stream"]
+    )
+    .unwrap();
+    static ref WS_RECONNECTS: IntCounterVec = IntCounterVec::new(
+        Opts::new("binance_ws_reconnects_total", "Websocket reconnections by stream"),
+        &["stream"]
+    )
+    .unwrap();
+    static ref WS_PARSE_FAILURES: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "binance_ws_parse_failures_total",
+            "Websocket messages which failed to parse by stream"
+        ),
+        &["stream"]
+    )
+    .unwrap();
+}
+
+/// Register every metric into `registry`
+pub fn register(registry: &Registry) -> prometheus::Result<()> {
+    registry.register(Box::new(REQUESTS.clone()))?;
+    registry.register(Box::new(REQUEST_DURATION.clone()))?;
+    registry.register(Box::new(ERROR_CODES.clone()))?;
+    registry.register(Box::new(WS_MESSAGES.clone()))?;
+    registry.register(Box::new(WS_RECONNECTS.clone()))?;
+    registry.register(Box::new(WS_PARSE_FAILURES.clone()))?;
+    Ok(())
+}
+
+/// Record a response, `status` is `None` when no response was received
+pub(crate) fn record_request(endpoint: &str, method: &str, status: Option<u16>, elapsed: Option<Duration>) {
+    let status = status.map_or_else(|| "none".to_string(), |s| s.to_string());
+    REQUESTS.with_label_values(&[endpoint, method, &status]).inc();
+    if let Some(elapsed) = elapsed {
+        REQUEST_DURATION
+            .with_label_values(&[endpoint])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Record the exchange error code of a failed request, if any
+pub(crate) fn record_error(endpoint: &str, error: &Error) {
+    if let Some(code) = error.code() {
+        ERROR_CODES
+            .with_label_values(&[endpoint, &code.code().to_string()])
+            .inc();
+    }
+}
+
+pub(crate) fn record_ws_message(stream: &str, msg: &str) {
+    WS_MESSAGES
+        .with_label_values(&[combined_stream_name(msg).unwrap_or(stream)])
+        .inc();
+}
+
+pub(crate) fn record_ws_reconnect(stream: &str) { WS_RECONNECTS.with_label_values(&[stream]).inc(); }
+
+pub(crate) fn record_ws_parse_failure(stream: &str, msg: &str) {
+    WS_PARSE_FAILURES
+        .with_label_values(&[combined_stream_name(msg).unwrap_or(stream)])
+        .inc();
+}
+
+/// The stream of a combined stream message, which starts with `{"stream":"<name>"`
+fn combined_stream_name(msg: &str) -> Option<&str> {
+    let rest = msg.strip_prefix(r#"{"stream":""#)?;
+    rest.split_once('"').map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combined_stream_name() {
+        assert_eq!(
+            combined_stream_name(r#"{"stream":"ethusdt@trade","data":{}}"#),
+            Some("ethusdt@trade")
+        );
+        assert_eq!(combined_stream_name(r#"{"e":"trade"}"#), None);
+    }
+
+    #[test]
+    fn test_register() {
+        let registry = Registry::new();
+        register(&registry).unwrap();
+        record_request("/api/v3/time", "GET", Some(200), Some(Duration::from_millis(20)));
+        record_ws_message("btcusdt@trade", "{}");
+        let names: Vec<String> = registry.gather().iter().map(|f| f.get_name().to_string()).collect();
+        assert!(names.contains(&"binance_requests_total".to_string()), "{names:?}");
+        assert!(names.contains(&"binance_ws_messages_total".to_string()), "{names:?}");
+        assert!(register(&registry).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+
+use crate::errors::*;
+
+static REDACTED: &str = "<redacted>";
+
+/// A request about to be sent. Middlewares may alter its url and headers in [`Middleware::before_send`].
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub host: String,
+    /// The endpoint path, such as `/api/v3/order`
+    pub endpoint: String,
+    /// The full url including the query string and, for signed requests, the signature
+    pub url: String,
+    pub headers: HeaderMap,
+}
+
+impl HttpRequest {
+    /// The url with the value of the `signature` parameter redacted, suitable for logs
+    pub fn redacted_url(&self) -> String {
+        match self.url.split_once('?') {
+            Some((path, query)) => {
+                let query = query
+                    .split('&')
+                    .map(|kv| match kv.split_once('=') {
+                        Some(("signature", _)) => format!("signature={REDACTED}"),
+                        _ => kv.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("&");
+                format!("{path}?{query}")
+            }
+            None => self.url.clone(),
+        }
+    }
+}
+
+/// A received response, whatever its status
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// The raw body
+    pub body: Bytes,
+    /// Time from sending the request until the whole body was received
+    pub elapsed: Duration,
+}
+
+/// Observes and alters the traffic of [`crate::client::Client`] and [`crate::client::GenericClient`].
+///
+/// Every hook has a default implementation doing nothing, implement only the ones needed.
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent, an error aborts the request
+    fn before_send(&self, _request: &mut HttpRequest) -> Result<()> { Ok(()) }
+
+    /// Called when a response is received, before its status is checked and its body parsed
+    fn after_receive(&self, _request: &HttpRequest, _response: &HttpResponse) {}
+
+    /// Called when a request fails, be it while sending it, because of its status or when parsing its body
+    fn on_error(&self, _request: &HttpRequest, _error: &Error) {}
+}
+
+/// The middlewares of a client, called in the order they were added
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl MiddlewareChain {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn push(&mut self, middleware: impl Middleware + 'static) { self.middlewares.push(Arc::new(middleware)); }
+
+    pub fn is_empty(&self) -> bool { self.middlewares.is_empty() }
+
+    pub fn len(&self) -> usize { self.middlewares.len() }
+}
+
+impl Middleware for MiddlewareChain {
+    fn before_send(&self, request: &mut HttpRequest) -> Result<()> {
+        self.middlewares.iter().try_for_each(|m| m.before_send(request))
+    }
+
+    fn after_receive(&self, request: &HttpRequest, response: &HttpResponse) {
+        self.middlewares.iter().for_each(|m| m.after_receive(request, response));
+    }
+
+    fn on_error(&self, request: &HttpRequest, error: &Error) {
+        self.middlewares.iter().for_each(|m| m.on_error(request, error));
+    }
+}
+
+impl fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareChain").field("len", &self.len()).finish()
+    }
+}
+
+/// Two chains are equal when they hold the same middlewares
+impl PartialEq for MiddlewareChain {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .middlewares
+                .iter()
+                .zip(&other.middlewares)
+                .all(|(a, b)| std::ptr::addr_eq(Arc::as_ptr(a), Arc::as_ptr(b)))
+    }
+}
+
+impl Eq for MiddlewareChain {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn http_request(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: Method::GET,
+            host: "https://api.binance.com".to_string(),
+            endpoint: "/api/v3/account".to_string(),
+            url: url.to_string(),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_redacted_url() {
+        let request =
+            http_request("https://api.binance.com/api/v3/account?recvWindow=5000&timestamp=1&signature=abcdef");
+        assert_eq!(
+            request.redacted_url(),
+            "https://api.binance.com/api/v3/account?recvWindow=5000&timestamp=1&signature=<redacted>"
+        );
+        let request = http_request("https://api.binance.com/api/v3/time");
+        assert_eq!(request.redacted_url(), request.url);
+    }
+
+    struct Recorder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Recorder {
+        fn before_send(&self, request: &mut HttpRequest) -> Result<()> {
+            self.calls.lock().unwrap().push(format!("{} before", self.name));
+            request.headers.insert("x-audit", self.name.parse().unwrap());
+            if self.name == "deny" {
+                return Err(Error::Msg("denied".to_string()));
+            }
+            Ok(())
+        }
+
+        fn on_error(&self, _request: &HttpRequest, error: &Error) {
+            self.calls.lock().unwrap().push(format!("{} error {error}", self.name));
+        }
+    }
+
+    #[test]
+    fn test_chain_order() {
+        let calls = Arc::new(Mutex::new(vec![]));
+        let mut chain = MiddlewareChain::new();
+        chain.push(Recorder {
+            name: "first",
+            calls: calls.clone(),
+        });
+        chain.push(Recorder {
+            name: "deny",
+            calls: calls.clone(),
+        });
+        chain.push(Recorder {
+            name: "last",
+            calls: calls.clone(),
+        });
+
+        let mut request = http_request("https://api.binance.com/api/v3/account");
+        let error = chain.before_send(&mut request).unwrap_err();
+        assert_eq!(request.headers.get("x-audit").unwrap(), "deny");
+        chain.on_error(&request, &error);
+        assert_eq!(*calls.lock().unwrap(), vec![
+            "first before",
+            "deny before",
+            "first error denied",
+            "deny error denied",
+            "last error denied"
+        ]);
+
+        assert_eq!(chain, chain.clone());
+        assert_ne!(chain, MiddlewareChain::new());
+    }
+}
@@ -0,0 +1,507 @@
+//! Local order books kept in sync from a depth snapshot and the diff depth stream.
+//!
+//! Follows the algorithm documented by Binance: the diff stream is opened first and buffered while
+//! a snapshot is fetched, events older than the snapshot are dropped, the first event applied must
+//! overlap the snapshot and every later event must follow the previous one. A gap in the sequence
+//! clears the book and fetches a new snapshot.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+
+use crate::api::Binance;
+use crate::config::Config;
+use crate::errors::*;
+#[cfg(feature = "futures_api")]
+use crate::futures::market::FuturesMarket;
+use crate::market::Market;
+use crate::rest_model::{Asks, Bids};
+use crate::websockets::{diff_book_depth_stream, SocketHandler};
+
+/// Levels of the snapshots
+static SNAPSHOT_DEPTH: u16 = 1000;
+/// Interval of the diff depth events in milliseconds
+static UPDATE_SPEED: u16 = 100;
+
+/// A change of a [`LocalOrderBook`]
+#[derive(Debug, Clone)]
+pub enum OrderBookEvent {
+    /// A snapshot was loaded
+    Snapshot { last_update_id: u64 },
+    /// A diff depth event was applied, a level with a quantity of 0 was removed
+    Update {
+        first_update_id: u64,
+        final_update_id: u64,
+        bids: Vec<Bids>,
+        asks: Vec<Asks>,
+    },
+    /// An event did not follow the last one applied, the book was cleared and will load a new snapshot
+    Gap { last_update_id: u64, first_update_id: u64 },
+}
+
+/// An order book of a spot or USD-M futures symbol, updated as the stream of its changes is polled.
+///
+/// Nothing is fetched until the stream is polled, the book is empty until the first
+/// [`OrderBookEvent::Snapshot`]. Use [`LocalOrderBook::view`] to read the book from other tasks.
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::config::Config;
+/// use binance::order_book::LocalOrderBook;
+/// use futures::StreamExt;
+///
+/// # async fn run() -> binance::errors::Result<()> {
+/// let mut book = LocalOrderBook::spot("BTCUSDT", &Config::default()).await?;
+/// while let Some(event) = book.next().await {
+///     event?;
+///     println!("{:?} {:?}", book.best_bid(), book.best_ask());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LocalOrderBook {
+    view: OrderBookView,
+    events: Pin<Box<dyn Stream<Item = Result<OrderBookEvent>> + Send>>,
+}
+
+impl LocalOrderBook {
+    /// Connects the diff depth stream of a spot symbol
+    pub async fn spot(symbol: &str, conf: &Config) -> Result<Self> {
+        let mut socket_handler = SocketHandler::new_with_options(conf.clone());
+        socket_handler
+            .connect(&diff_book_depth_stream(&symbol.to_lowercase(), UPDATE_SPEED))
+            .await?;
+        let diffs = socket_handler
+            .into_stream::<crate::ws_model::DepthOrderBookEvent>()
+            .map(|event| event.map(DepthDiff::from));
+        let snapshots = Snapshots::Spot(Market::new_with_config(None, None, conf));
+        Ok(Self::new(symbol, snapshots, diffs, false))
+    }
+
+    /// Connects the diff depth stream of a USD-M futures symbol
+    #[cfg(feature = "futures_api")]
+    pub async fn usd_m(symbol: &str, conf: &Config) -> Result<Self> {
+        let mut socket_handler = SocketHandler::new_with_options(conf.clone());
+        socket_handler
+            .connect_futures(&diff_book_depth_stream(&symbol.to_lowercase(), UPDATE_SPEED))
+            .await?;
+        let diffs = socket_handler
+            .into_stream::<crate::futures::ws_model::DepthOrderBookEvent>()
+            .map(|event| event.map(DepthDiff::from));
+        let snapshots = Snapshots::UsdM(FuturesMarket::new_with_config(None, None, conf));
+        Ok(Self::new(symbol, snapshots, diffs, true))
+    }
+
+    fn new(
+        symbol: &str,
+        snapshots: Snapshots,
+        diffs: impl Stream<Item = Result<DepthDiff>> + Send + 'static,
+        futures: bool,
+    ) -> Self {
+        let view = OrderBookView(Arc::new(RwLock::new(Book::new(futures))));
+        let sync = BookSync {
+            symbol: symbol.to_uppercase(),
+            snapshots,
+            diffs: Box::pin(diffs),
+            view: view.clone(),
+        };
+        let events = futures::stream::unfold(sync, |mut sync| async move {
+            let event = sync.next().await?;
+            Some((event, sync))
+        });
+        LocalOrderBook {
+            view,
+            events: Box::pin(events),
+        }
+    }
+
+    /// A handle reading the book, which stays up to date as long as this stream is polled
+    pub fn view(&self) -> OrderBookView { self.view.clone() }
+
+    pub fn best_bid(&self) -> Option<Bids> { self.view.best_bid() }
+
+    pub fn best_ask(&self) -> Option<Asks> { self.view.best_ask() }
+
+    /// The best `levels` bids, highest price first
+    pub fn bids(&self, levels: usize) -> Vec<Bids> { self.view.bids(levels) }
+
+    /// The best `levels` asks, lowest price first
+    pub fn asks(&self, levels: usize) -> Vec<Asks> { self.view.asks(levels) }
+
+    pub fn last_update_id(&self) -> Option<u64> { self.view.last_update_id() }
+}
+
+impl Stream for LocalOrderBook {
+    type Item = Result<OrderBookEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.as_mut().poll_next(cx)
+    }
+}
+
+/// A shared, read only handle of a [`LocalOrderBook`]
+#[derive(Clone)]
+pub struct OrderBookView(Arc<RwLock<Book>>);
+
+impl OrderBookView {
+    pub fn best_bid(&self) -> Option<Bids> { self.bids(1).pop() }
+
+    pub fn best_ask(&self) -> Option<Asks> { self.asks(1).pop() }
+
+    /// The best `levels` bids, highest price first
+    pub fn bids(&self, levels: usize) -> Vec<Bids> {
+        self.read()
+            .bids
+            .iter()
+            .rev()
+            .take(levels)
+            .map(|(price, qty)| Bids {
+                price: price.0,
+                qty: *qty,
+            })
+            .collect()
+    }
+
+    /// The best `levels` asks, lowest price first
+    pub fn asks(&self, levels: usize) -> Vec<Asks> {
+        self.read()
+            .asks
+            .iter()
+            .take(levels)
+            .map(|(price, qty)| Asks {
+                price: price.0,
+                qty: *qty,
+            })
+            .collect()
+    }
+
+    /// The id of the last update applied, `None` until a snapshot is loaded
+    pub fn last_update_id(&self) -> Option<u64> { self.read().last_update_id }
+
+    fn read(&self) -> RwLockReadGuard<'_, Book> { self.0.read().unwrap_or_else(|e| e.into_inner()) }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Book> { self.0.write().unwrap_or_else(|e| e.into_inner()) }
+}
+
+enum Snapshots {
+    Spot(Market),
+    #[cfg(feature = "futures_api")]
+    UsdM(FuturesMarket),
+}
+
+impl Snapshots {
+    async fn fetch(&self, symbol: &str) -> Result<(u64, Vec<Bids>, Vec<Asks>)> {
+        match self {
+            Snapshots::Spot(market) => {
+                let book = market.get_custom_depth(symbol, SNAPSHOT_DEPTH).await?;
+                Ok((book.last_update_id, book.bids, book.asks))
+            }
+            #[cfg(feature = "futures_api")]
+            Snapshots::UsdM(market) => {
+                let book = market.get_custom_depth(symbol, SNAPSHOT_DEPTH).await?;
+                Ok((book.last_update_id, book.bids, book.asks))
+            }
+        }
+    }
+}
+
+/// The fields of spot and futures diff depth events needed to sequence them
+struct DepthDiff {
+    first_update_id: u64,
+    final_update_id: u64,
+    previous_final_update_id: Option<u64>,
+    bids: Vec<Bids>,
+    asks: Vec<Asks>,
+}
+
+impl From<crate::ws_model::DepthOrderBookEvent> for DepthDiff {
+    fn from(event: crate::ws_model::DepthOrderBookEvent) -> Self {
+        DepthDiff {
+            first_update_id: event.first_update_id,
+            final_update_id: event.final_update_id,
+            previous_final_update_id: None,
+            bids: event.bids,
+            asks: event.asks,
+        }
+    }
+}
+
+#[cfg(feature = "futures_api")]
+impl From<crate::futures::ws_model::DepthOrderBookEvent> for DepthDiff {
+    fn from(event: crate::futures::ws_model::DepthOrderBookEvent) -> Self {
+        DepthDiff {
+            first_update_id: event.first_update_id,
+            final_update_id: event.final_update_id,
+            previous_final_update_id: Some(event.previous_final_update_id),
+            bids: event.bids,
+            asks: event.asks,
+        }
+    }
+}
+
+struct BookSync {
+    symbol: String,
+    snapshots: Snapshots,
+    diffs: Pin<Box<dyn Stream<Item = Result<DepthDiff>> + Send>>,
+    view: OrderBookView,
+}
+
+impl BookSync {
+    /// The next change of the book, `None` once the diff stream ends
+    async fn next(&mut self) -> Option<Result<OrderBookEvent>> {
+        if self.view.last_update_id().is_none() {
+            // Diff events are buffered by the socket until the snapshot is loaded
+            let snapshot = self.snapshots.fetch(&self.symbol).await;
+            return Some(snapshot.map(|(last_update_id, bids, asks)| {
+                self.view.write().load(last_update_id, bids, asks);
+                OrderBookEvent::Snapshot { last_update_id }
+            }));
+        }
+        loop {
+            let diff = match self.diffs.next().await? {
+                Ok(diff) => diff,
+                Err(e) => return Some(Err(e)),
+            };
+            let mut book = self.view.write();
+            match book.apply(&diff) {
+                Sequence::Stale => continue,
+                Sequence::Applied => {
+                    return Some(Ok(OrderBookEvent::Update {
+                        first_update_id: diff.first_update_id,
+                        final_update_id: diff.final_update_id,
+                        bids: diff.bids,
+                        asks: diff.asks,
+                    }))
+                }
+                Sequence::Gap => {
+                    let last_update_id = book.last_update_id.unwrap_or_default();
+                    *book = Book::new(book.futures);
+                    return Some(Ok(OrderBookEvent::Gap {
+                        last_update_id,
+                        first_update_id: diff.first_update_id,
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// Orders prices for the levels of a book
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering { self.0.total_cmp(&other.0) }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Sequence {
+    /// The event is older than the book
+    Stale,
+    Applied,
+    /// Events between the book and this one are missing
+    Gap,
+}
+
+#[derive(Debug)]
+struct Book {
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    last_update_id: Option<u64>,
+    /// Whether an event was applied since the snapshot
+    updated: bool,
+    /// Futures events are sequenced with the final update id of the previous event
+    futures: bool,
+}
+
+impl Book {
+    fn new(futures: bool) -> Self {
+        Book {
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_update_id: None,
+            updated: false,
+            futures,
+        }
+    }
+
+    fn load(&mut self, last_update_id: u64, bids: Vec<Bids>, asks: Vec<Asks>) {
+        *self = Book::new(self.futures);
+        self.last_update_id = Some(last_update_id);
+        self.update(bids, asks);
+    }
+
+    fn apply(&mut self, diff: &DepthDiff) -> Sequence {
+        let Some(last) = self.last_update_id else {
+            return Sequence::Stale;
+        };
+        let (first, fin) = (diff.first_update_id, diff.final_update_id);
+        let sequence = match (self.futures, self.updated) {
+            (false, _) if fin <= last => Sequence::Stale,
+            (false, false) if first <= last + 1 => Sequence::Applied,
+            (false, true) if first == last + 1 => Sequence::Applied,
+            (true, _) if fin < last => Sequence::Stale,
+            (true, false) if first <= last => Sequence::Applied,
+            (true, true) if diff.previous_final_update_id == Some(last) => Sequence::Applied,
+            _ => Sequence::Gap,
+        };
+        if sequence == Sequence::Applied {
+            self.last_update_id = Some(fin);
+            self.updated = true;
+            self.update(diff.bids.clone(), diff.asks.clone());
+        }
+        sequence
+    }
+
+    fn update(&mut self, bids: Vec<Bids>, asks: Vec<Asks>) {
+        bids.into_iter()
+            .for_each(|bid| update_level(&mut self.bids, bid.price, bid.qty));
+        asks.into_iter()
+            .for_each(|ask| update_level(&mut self.asks, ask.price, ask.qty));
+    }
+}
+
+fn update_level(side: &mut BTreeMap<Price, f64>, price: f64, qty: f64) {
+    if qty == 0.0 {
+        side.remove(&Price(price));
+    } else {
+        side.insert(Price(price), qty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    fn diff(first: u64, fin: u64, previous: Option<u64>, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> DepthDiff {
+        DepthDiff {
+            first_update_id: first,
+            final_update_id: fin,
+            previous_final_update_id: previous,
+            bids: bids.iter().map(|&(price, qty)| Bids { price, qty }).collect(),
+            asks: asks.iter().map(|&(price, qty)| Asks { price, qty }).collect(),
+        }
+    }
+
+    fn book(futures: bool) -> Book {
+        let mut book = Book::new(futures);
+        book.load(
+            100,
+            vec![Bids { price: 10.0, qty: 1.0 }, Bids { price: 9.0, qty: 2.0 }],
+            vec![Asks { price: 11.0, qty: 1.0 }],
+        );
+        book
+    }
+
+    #[test]
+    fn test_spot_sequence() {
+        let mut book = book(false);
+        assert_eq!(book.apply(&diff(90, 100, None, &[(10.0, 5.0)], &[])), Sequence::Stale);
+        assert_eq!(book.apply(&diff(102, 105, None, &[], &[])), Sequence::Gap);
+        assert_eq!(
+            book.apply(&diff(95, 103, None, &[(10.0, 0.0)], &[(12.0, 3.0)])),
+            Sequence::Applied
+        );
+        assert_eq!(book.last_update_id, Some(103));
+        assert_eq!(book.apply(&diff(105, 106, None, &[], &[])), Sequence::Gap);
+        assert_eq!(book.apply(&diff(104, 106, None, &[(9.5, 1.0)], &[])), Sequence::Applied);
+
+        let view = OrderBookView(Arc::new(RwLock::new(book)));
+        assert_eq!(view.best_bid().unwrap().price, 9.5);
+        assert_eq!(view.best_ask().unwrap().price, 11.0);
+        let bids: Vec<f64> = view.bids(5).iter().map(|bid| bid.price).collect();
+        assert_eq!(bids, vec![9.5, 9.0]);
+        let asks: Vec<f64> = view.asks(1).iter().map(|ask| ask.price).collect();
+        assert_eq!(asks, vec![11.0]);
+    }
+
+    #[test]
+    fn test_futures_sequence() {
+        let mut book = book(true);
+        assert_eq!(book.apply(&diff(90, 99, Some(89), &[], &[])), Sequence::Stale);
+        assert_eq!(book.apply(&diff(101, 105, Some(100), &[], &[])), Sequence::Gap);
+        assert_eq!(
+            book.apply(&diff(95, 100, Some(94), &[], &[(11.0, 0.0)])),
+            Sequence::Applied
+        );
+        assert_eq!(book.apply(&diff(104, 106, Some(101), &[], &[])), Sequence::Gap);
+        assert_eq!(book.apply(&diff(104, 106, Some(100), &[], &[])), Sequence::Applied);
+        assert_eq!(book.last_update_id, Some(106));
+        assert!(book.asks.is_empty());
+    }
+
+    fn snapshot(last_update_id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "lastUpdateId": last_update_id,
+            "bids": [["10.0", "1.0"]],
+            "asks": [["11.0", "1.0"]],
+        })
+    }
+
+    async fn serve_diffs(listener: TcpListener) {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        for (first, fin, bid) in [(90, 100, "9.0"), (99, 101, "9.5"), (105, 106, "9.6"), (199, 201, "9.7")] {
+            let event = serde_json::json!({
+                "e": "depthUpdate", "E": 1, "s": "BTCUSDT", "U": first, "u": fin,
+                "b": [[bid, "1.0"]], "a": [],
+            });
+            socket.send(Message::Text(event.to_string())).await.unwrap();
+        }
+        socket.next().await;
+    }
+
+    #[tokio::test]
+    async fn test_resync_on_gap() {
+        // A snapshot per request, its last update id being 100 then 200
+        let (url, _) = crate::test_util::serve_bodies([snapshot(100), snapshot(200)]).await;
+        let ws = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default()
+            .set_rest_api_endpoint(url)
+            .set_ws_endpoint(format!("ws://{}", ws.local_addr().unwrap()));
+        tokio::spawn(serve_diffs(ws));
+
+        let mut book = LocalOrderBook::spot("btcusdt", &config).await.unwrap();
+        let mut events = vec![];
+        for _ in 0..5 {
+            let event = tokio::time::timeout(Duration::from_secs(5), book.next()).await.unwrap();
+            events.push(event.unwrap().unwrap());
+        }
+        assert!(matches!(events[0], OrderBookEvent::Snapshot { last_update_id: 100 }));
+        assert!(matches!(events[1], OrderBookEvent::Update {
+            final_update_id: 101,
+            ..
+        }));
+        assert!(matches!(events[2], OrderBookEvent::Gap {
+            last_update_id: 101,
+            first_update_id: 105
+        }));
+        assert!(matches!(events[3], OrderBookEvent::Snapshot { last_update_id: 200 }));
+        assert!(matches!(events[4], OrderBookEvent::Update {
+            final_update_id: 201,
+            ..
+        }));
+        assert_eq!(book.last_update_id(), Some(201));
+        let bids: Vec<f64> = book.bids(5).iter().map(|bid| bid.price).collect();
+        assert_eq!(bids, vec![10.0, 9.7]);
+    }
+}
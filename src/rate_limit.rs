@@ -0,0 +1,594 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+
+use crate::errors::*;
+use crate::rest_model::{RateLimit, RateLimitInterval, RateLimitType};
+
+static USED_WEIGHT_HEADER_PREFIX: &str = "x-mbx-used-weight-";
+static ORDER_COUNT_HEADER_PREFIX: &str = "x-mbx-order-count-";
+
+/// What to do when a request would exceed a known rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RateLimitPolicy {
+    /// Wait until the current window resets before sending the request
+    #[default]
+    Delay,
+    /// Fail fast with [`Error::RateLimitExceeded`]
+    Reject,
+    /// Only track usage, never hold back requests
+    TrackOnly,
+}
+
+/// Opt-in retries of idempotent GET requests rejected with HTTP 429 or 418.
+/// Requests sent with any other method, such as order placement, are never retried.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries of a single request
+    pub max_retries: u32,
+    /// Wait used when the server does not send a `Retry-After` header
+    pub default_wait: Duration,
+    /// Give up instead of waiting out a ban longer than this
+    pub max_wait: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            default_wait: Duration::from_secs(1),
+            max_wait: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns how long to wait before retrying, or `None` if the request must not be retried
+    pub fn backoff(&self, method: &Method, error: &Error, attempt: u32) -> Option<Duration> {
+        if method != Method::GET || attempt >= self.max_retries {
+            return None;
+        }
+        let wait = match error {
+            Error::TooManyRequests { retry_after, .. } | Error::IpBanned { retry_after, .. } => {
+                retry_after.unwrap_or(self.default_wait)
+            }
+            _ => return None,
+        };
+        (wait <= self.max_wait).then_some(wait)
+    }
+}
+
+/// Usage of a single rate limit window for a host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitUsage {
+    pub rate_limit_type: RateLimitType,
+    pub interval: Duration,
+    /// Weight or order count used in the current window
+    pub used: u32,
+    /// Limit seeded from the exchange information, if any
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counter {
+    used: u32,
+    window_start: u64,
+}
+
+#[derive(Debug, Default)]
+struct HostState {
+    limits: HashMap<(RateLimitType, Duration), u32>,
+    counters: HashMap<(RateLimitType, Duration), Counter>,
+}
+
+/// Tracks used weight and order counts per host from the `X-MBX-USED-WEIGHT-*` and
+/// `X-MBX-ORDER-COUNT-*` response headers, and holds back requests that would exceed a limit.
+///
+/// Requests in flight are counted with their [`RequestCost`] until the headers of their responses
+/// report the actual usage. Requests are only held back once the limits of the host are seeded,
+/// see [`RateLimiter::set_limits`].
+///
+/// Cloning a [`RateLimiter`] shares its state, so every client created from the same
+/// [`crate::config::Config`] accounts for the same budget.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    policy: RateLimitPolicy,
+    hosts: Arc<Mutex<HashMap<String, HostState>>>,
+}
+
+impl RateLimiter {
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy,
+            hosts: Default::default(),
+        }
+    }
+
+    pub fn policy(&self) -> RateLimitPolicy { self.policy }
+
+    /// Seed the limits of `host`, usually from `ExchangeInformation::rate_limits`
+    pub fn set_limits(&self, host: &str, rate_limits: &[RateLimit]) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        state.limits = rate_limits
+            .iter()
+            .filter_map(|rl| {
+                Some((
+                    (rl.rate_limit_type.clone(), limit_interval(rl)?),
+                    rl.limit.max(0) as u32,
+                ))
+            })
+            .collect();
+    }
+
+    /// Record the usage reported by the headers of a response from `host`
+    pub fn update_from_headers(&self, host: &str, headers: &HeaderMap) {
+        self.update_from_headers_at(host, headers, now_millis())
+    }
+
+    fn update_from_headers_at(&self, host: &str, headers: &HeaderMap, now: u64) {
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry(host.to_string()).or_default();
+        for (name, value) in headers {
+            let Some(key) = parse_header_name(name.as_str()) else {
+                continue;
+            };
+            let Some(used) = value.to_str().ok().and_then(|v| v.parse::<u32>().ok()) else {
+                continue;
+            };
+            let window_start = window_start(now, key.1);
+            let counter = state.counters.entry(key).or_default();
+            // Usage only grows within a window, responses may arrive out of order
+            if counter.window_start != window_start {
+                *counter = Counter { used, window_start };
+            } else {
+                counter.used = counter.used.max(used);
+            }
+        }
+    }
+
+    /// Current usage of every window seen or seeded for `host`
+    pub fn usage(&self, host: &str) -> Vec<RateLimitUsage> {
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .get(host)
+            .map(|state| state.usage(now_millis()))
+            .unwrap_or_default()
+    }
+
+    /// Current usage of every known host
+    pub fn snapshot(&self) -> HashMap<String, Vec<RateLimitUsage>> {
+        let now = now_millis();
+        let hosts = self.hosts.lock().unwrap();
+        hosts
+            .iter()
+            .map(|(host, state)| (host.clone(), state.usage(now)))
+            .collect()
+    }
+
+    /// Whether the limits of `host` are seeded
+    pub fn has_limits(&self, host: &str) -> bool {
+        let hosts = self.hosts.lock().unwrap();
+        hosts.get(host).is_some_and(|state| !state.limits.is_empty())
+    }
+
+    /// Wait for, or reject, a request of `cost` on `host` according to the policy
+    pub async fn acquire(&self, host: &str, cost: RequestCost) -> Result<()> {
+        if self.policy == RateLimitPolicy::TrackOnly {
+            return Ok(());
+        }
+        loop {
+            let wait = self.try_acquire_at(host, cost, now_millis());
+            match (wait, self.policy) {
+                (Ok(()), _) => return Ok(()),
+                (Err(e), RateLimitPolicy::Reject) => return Err(e),
+                (Err(Error::RateLimitExceeded { retry_after, .. }), _) => tokio::time::sleep(retry_after).await,
+                (Err(e), _) => return Err(e),
+            }
+        }
+    }
+
+    fn try_acquire_at(&self, host: &str, cost: RequestCost, now: u64) -> Result<()> {
+        let mut hosts = self.hosts.lock().unwrap();
+        let Some(state) = hosts.get_mut(host) else {
+            return Ok(());
+        };
+        for ((rate_limit_type, interval), limit) in &state.limits {
+            let counted = cost.counted(rate_limit_type);
+            if counted == 0 {
+                continue;
+            }
+            let used = state
+                .counters
+                .get(&(rate_limit_type.clone(), *interval))
+                .filter(|c| c.window_start == window_start(now, *interval))
+                .map(|c| c.used)
+                .unwrap_or(0);
+            // A request heavier than the limit goes through alone in its window
+            if used > 0 && used + counted > *limit {
+                let window_end = window_start(now, *interval) + interval.as_millis() as u64;
+                return Err(Error::RateLimitExceeded {
+                    rate_limit_type: rate_limit_type.clone(),
+                    interval: *interval,
+                    used,
+                    limit: *limit,
+                    retry_after: Duration::from_millis(window_end.saturating_sub(now)),
+                });
+            }
+        }
+        // Count the request now so concurrent callers see it before the response arrives
+        let keys: Vec<_> = state.limits.keys().cloned().collect();
+        for key in keys {
+            state.counters.entry(key).or_default();
+        }
+        for ((rate_limit_type, interval), counter) in state.counters.iter_mut() {
+            let start = window_start(now, *interval);
+            if counter.window_start != start {
+                *counter = Counter {
+                    used: 0,
+                    window_start: start,
+                };
+            }
+            counter.used += cost.counted(rate_limit_type);
+        }
+        Ok(())
+    }
+}
+
+impl HostState {
+    fn usage(&self, now: u64) -> Vec<RateLimitUsage> {
+        let mut usage: Vec<RateLimitUsage> = self
+            .counters
+            .iter()
+            .map(|(key, counter)| {
+                (
+                    key,
+                    counter.used * (counter.window_start == window_start(now, key.1)) as u32,
+                )
+            })
+            .chain(
+                self.limits
+                    .keys()
+                    .filter(|key| !self.counters.contains_key(key))
+                    .map(|key| (key, 0)),
+            )
+            .map(|((rate_limit_type, interval), used)| RateLimitUsage {
+                rate_limit_type: rate_limit_type.clone(),
+                interval: *interval,
+                used,
+                limit: self.limits.get(&(rate_limit_type.clone(), *interval)).copied(),
+            })
+            .collect();
+        usage.sort_by_key(|u| (u.rate_limit_type == RateLimitType::Orders, u.interval));
+        usage
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter").field("policy", &self.policy).finish()
+    }
+}
+
+/// Two limiters are equal when they share the same state
+impl PartialEq for RateLimiter {
+    fn eq(&self, other: &Self) -> bool { self.policy == other.policy && Arc::ptr_eq(&self.hosts, &other.hosts) }
+}
+
+impl Eq for RateLimiter {}
+
+/// The weight and order count of a request.
+///
+/// Weights are the documented ones of the heavier market and account endpoints of the spot and
+/// futures APIs, 1 for the other endpoints, so the count of requests in flight is approximate
+/// until the response headers report the actual usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestCost {
+    pub weight: u32,
+    /// Orders placed, replaced or amended by the request
+    pub orders: u32,
+}
+
+impl RequestCost {
+    /// The cost of a request of `method` to `endpoint`, `url` carrying its query
+    pub fn of(method: &Method, endpoint: &str, url: &str) -> Self {
+        let query = url.split_once('?').map(|(_, query)| query).unwrap_or_default();
+        let params: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+        Self {
+            weight: request_weight(method, endpoint, &params),
+            orders: order_count(method, endpoint, &params),
+        }
+    }
+
+    fn counted(&self, rate_limit_type: &RateLimitType) -> u32 {
+        match rate_limit_type {
+            RateLimitType::RequestWeight => self.weight,
+            RateLimitType::Orders => self.orders,
+            _ => 0,
+        }
+    }
+}
+
+fn request_weight(method: &Method, endpoint: &str, params: &HashMap<String, String>) -> u32 {
+    let limit = params.get("limit").and_then(|limit| limit.parse::<u32>().ok());
+    let with_symbol = params.contains_key("symbol") || params.contains_key("symbols");
+    let api = endpoint.split('/').nth(1).unwrap_or_default();
+    let name = endpoint.rsplit('/').next().unwrap_or_default();
+    match (api, name) {
+        ("api", "klines" | "uiKlines" | "aggTrades") => 2,
+        ("api", "depth") => match limit.unwrap_or(100) {
+            0..=100 => 5,
+            101..=500 => 25,
+            501..=1000 => 50,
+            _ => 250,
+        },
+        ("api", "trades" | "historicalTrades") => 25,
+        ("api", "exchangeInfo" | "account" | "myTrades" | "allOrders") => 20,
+        ("api", "24hr") => {
+            if with_symbol {
+                2
+            } else {
+                80
+            }
+        }
+        ("api", "openOrders") if method == Method::GET => {
+            if with_symbol {
+                6
+            } else {
+                80
+            }
+        }
+        (
+            "fapi" | "dapi",
+            "klines" | "continuousKlines" | "indexPriceKlines" | "markPriceKlines" | "premiumIndexKlines",
+        ) => match limit.unwrap_or(500) {
+            0..=99 => 1,
+            100..=499 => 2,
+            500..=1000 => 5,
+            _ => 10,
+        },
+        ("fapi" | "dapi", "depth") => match limit.unwrap_or(500) {
+            0..=50 => 2,
+            51..=100 => 5,
+            101..=500 => 10,
+            _ => 20,
+        },
+        ("fapi" | "dapi", "aggTrades" | "historicalTrades" | "commissionRate") => 20,
+        ("fapi" | "dapi", "trades" | "allOrders" | "userTrades" | "account" | "adlQuantile") => 5,
+        ("fapi" | "dapi", "income") => 30,
+        ("fapi" | "dapi", "forceOrders") => {
+            if with_symbol {
+                20
+            } else {
+                50
+            }
+        }
+        ("fapi" | "dapi", "24hr" | "openOrders") => {
+            if with_symbol {
+                1
+            } else {
+                40
+            }
+        }
+        ("fapi" | "dapi", "batchOrders") if method != Method::DELETE => 5,
+        _ => 1,
+    }
+}
+
+/// Orders are placed, replaced or amended with POST or PUT on an order endpoint, a batch counting
+/// each of its orders and an order list each of its legs
+fn order_count(method: &Method, endpoint: &str, params: &HashMap<String, String>) -> u32 {
+    if (method != Method::POST && method != Method::PUT) || endpoint.ends_with("/test") {
+        return 0;
+    }
+    match endpoint.rsplit('/').next().unwrap_or_default() {
+        "order" | "cancelReplace" => 1,
+        "oco" | "oto" => 2,
+        "otoco" => 3,
+        "batchOrders" => params
+            .get("batchOrders")
+            .and_then(|orders| serde_json::from_str::<Vec<serde_json::Value>>(orders).ok())
+            .map_or(1, |orders| orders.len() as u32),
+        _ => 0,
+    }
+}
+
+fn limit_interval(rate_limit: &RateLimit) -> Option<Duration> {
+    let unit = match rate_limit.interval {
+        RateLimitInterval::Second => 1,
+        RateLimitInterval::Minute => 60,
+        RateLimitInterval::Day => 86_400,
+    };
+    (rate_limit.interval_num > 0).then(|| Duration::from_secs(unit * rate_limit.interval_num as u64))
+}
+
+/// Parses `x-mbx-used-weight-1m` or `x-mbx-order-count-10s` into a counter key
+fn parse_header_name(name: &str) -> Option<(RateLimitType, Duration)> {
+    let (rate_limit_type, suffix) = if let Some(suffix) = name.strip_prefix(USED_WEIGHT_HEADER_PREFIX) {
+        (RateLimitType::RequestWeight, suffix)
+    } else if let Some(suffix) = name.strip_prefix(ORDER_COUNT_HEADER_PREFIX) {
+        (RateLimitType::Orders, suffix)
+    } else {
+        return None;
+    };
+    let (num, unit) = suffix.split_at(suffix.len().checked_sub(1)?);
+    let unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        _ => return None,
+    };
+    let num: u64 = num.parse().ok()?;
+    (num > 0).then(|| (rate_limit_type, Duration::from_secs(num * unit)))
+}
+
+/// Binance windows are aligned on the clock, a minute window resets on the minute
+fn window_start(now: u64, interval: Duration) -> u64 {
+    let interval = (interval.as_millis() as u64).max(1);
+    now - now % interval
+}
+
+fn now_millis() -> u64 { Utc::now().timestamp_millis() as u64 }
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    use super::*;
+
+    static HOST: &str = "https://api.binance.com";
+
+    fn limits() -> Vec<RateLimit> {
+        vec![
+            RateLimit {
+                interval: RateLimitInterval::Minute,
+                rate_limit_type: RateLimitType::RequestWeight,
+                interval_num: 1,
+                limit: 6000,
+            },
+            RateLimit {
+                interval: RateLimitInterval::Second,
+                rate_limit_type: RateLimitType::Orders,
+                interval_num: 10,
+                limit: 2,
+            },
+        ]
+    }
+
+    fn headers(weight: &str, orders: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("x-mbx-used-weight-1m"),
+            HeaderValue::from_str(weight).unwrap(),
+        );
+        headers.insert(
+            HeaderName::from_static("x-mbx-order-count-10s"),
+            HeaderValue::from_str(orders).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_parse_header_name() {
+        assert_eq!(
+            parse_header_name("x-mbx-used-weight-1m"),
+            Some((RateLimitType::RequestWeight, Duration::from_secs(60)))
+        );
+        assert_eq!(
+            parse_header_name("x-mbx-order-count-10s"),
+            Some((RateLimitType::Orders, Duration::from_secs(10)))
+        );
+        assert_eq!(
+            parse_header_name("x-mbx-order-count-1d"),
+            Some((RateLimitType::Orders, Duration::from_secs(86_400)))
+        );
+        assert_eq!(parse_header_name("x-mbx-used-weight"), None);
+        assert_eq!(parse_header_name("content-type"), None);
+    }
+
+    #[test]
+    fn test_usage_from_headers() {
+        let limiter = RateLimiter::default();
+        limiter.set_limits(HOST, &limits());
+        limiter.update_from_headers(HOST, &headers("42", "1"));
+        let usage = limiter.usage(HOST);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].rate_limit_type, RateLimitType::RequestWeight);
+        assert_eq!(usage[0].used, 42);
+        assert_eq!(usage[0].limit, Some(6000));
+        assert_eq!(usage[1].rate_limit_type, RateLimitType::Orders);
+        assert_eq!(usage[1].used, 1);
+        assert_eq!(usage[1].limit, Some(2));
+        assert!(limiter.snapshot().contains_key(HOST));
+    }
+
+    #[test]
+    fn test_order_limit_rejects_until_window_resets() {
+        let limiter = RateLimiter::new(RateLimitPolicy::Reject);
+        limiter.set_limits(HOST, &limits());
+        let now = 1_700_000_003_000;
+        limiter.update_from_headers_at(HOST, &headers("10", "2"), now);
+
+        // Non order requests only count against the weight
+        assert!(limiter.try_acquire_at(HOST, cost(1, 0), now).is_ok());
+        match limiter.try_acquire_at(HOST, cost(1, 1), now) {
+            Err(Error::RateLimitExceeded { retry_after, used, .. }) => {
+                assert_eq!(used, 2);
+                assert_eq!(retry_after, Duration::from_secs(7));
+            }
+            r => panic!("expected rate limit error, got {r:?}"),
+        }
+        assert!(limiter.try_acquire_at(HOST, cost(1, 1), now + 7_000).is_ok());
+    }
+
+    fn cost(weight: u32, orders: u32) -> RequestCost { RequestCost { weight, orders } }
+
+    #[test]
+    fn test_counts_request_weight_in_flight() {
+        let limiter = RateLimiter::new(RateLimitPolicy::Reject);
+        limiter.set_limits(HOST, &limits());
+        let now = 1_700_000_003_000;
+
+        // Seeded limits apply before any response reported the usage
+        for _ in 0..600 {
+            assert!(limiter.try_acquire_at(HOST, cost(10, 0), now).is_ok());
+        }
+        assert!(limiter.try_acquire_at(HOST, cost(10, 0), now).is_err());
+        assert!(limiter.try_acquire_at(HOST, cost(10, 0), now + 60_000).is_ok());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::default();
+        let too_many = Error::TooManyRequests {
+            retry_after: Some(Duration::from_secs(5)),
+            code: Some(-1003),
+        };
+        assert_eq!(policy.backoff(&Method::GET, &too_many, 0), Some(Duration::from_secs(5)));
+        assert_eq!(policy.backoff(&Method::POST, &too_many, 0), None);
+        assert_eq!(policy.backoff(&Method::GET, &too_many, 3), None);
+        let banned = Error::IpBanned {
+            retry_after: Some(Duration::from_secs(3600)),
+            code: None,
+        };
+        assert_eq!(policy.backoff(&Method::GET, &banned, 0), None);
+        assert_eq!(policy.backoff(&Method::GET, &Error::InternalServerError, 0), None);
+    }
+
+    #[test]
+    fn test_request_cost() {
+        let of = |method, endpoint: &str, query: &str| {
+            RequestCost::of(&method, endpoint, &format!("{HOST}{endpoint}?{query}"))
+        };
+        assert_eq!(of(Method::POST, "/api/v3/order", "symbol=BTCUSDT"), cost(1, 1));
+        assert_eq!(of(Method::POST, "/api/v3/order/test", "symbol=BTCUSDT"), cost(1, 0));
+        assert_eq!(
+            of(Method::POST, "/api/v3/orderList/otoco", "symbol=BTCUSDT"),
+            cost(1, 3)
+        );
+        assert_eq!(of(Method::DELETE, "/api/v3/order", "symbol=BTCUSDT"), cost(1, 0));
+        assert_eq!(of(Method::GET, "/api/v3/openOrders", ""), cost(80, 0));
+        assert_eq!(of(Method::GET, "/fapi/v1/orderAmendment", "symbol=BTCUSDT"), cost(1, 0));
+        assert_eq!(
+            of(Method::GET, "/fapi/v1/klines", "symbol=BTCUSDT&limit=1500"),
+            cost(10, 0)
+        );
+        assert_eq!(of(Method::GET, "/fapi/v1/klines", "symbol=BTCUSDT"), cost(5, 0));
+        assert_eq!(
+            of(Method::GET, "/api/v3/depth", "symbol=BTCUSDT&limit=5000"),
+            cost(250, 0)
+        );
+        let batch: String =
+            url::form_urlencoded::byte_serialize(br#"[{"symbol":"BTCUSDT"},{"symbol":"ETHUSDT"}]"#).collect();
+        assert_eq!(
+            of(Method::PUT, "/fapi/v1/batchOrders", &format!("batchOrders={batch}")),
+            cost(5, 2)
+        );
+    }
+}
@@ -1329,7 +1329,7 @@ pub enum ContingencyType {
 ///   "limit": 1200
 /// }
 ///
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RateLimitType {
     RequestWeight,
@@ -1424,6 +1424,48 @@ pub struct KlineSummary {
     pub ignore: Option<String>,
 }
 
+impl TryFrom<&[serde_json::Value]> for KlineSummary {
+    type Error = crate::errors::Error;
+
+    /// Parse a row of a klines response. Mark price, index price and premium index klines have
+    /// the same columns, with zeros as volumes.
+    fn try_from(row: &[serde_json::Value]) -> crate::errors::Result<Self> {
+        use crate::util::{to_f64, to_i64};
+        if row.len() < 11 {
+            return Err(crate::errors::Error::Msg(format!(
+                "expected at least 11 kline columns, got {}",
+                row.len()
+            )));
+        }
+        Ok(KlineSummary {
+            open_time: to_i64(&row[0])?,
+            open: to_f64(&row[1])?,
+            high: to_f64(&row[2])?,
+            low: to_f64(&row[3])?,
+            close: to_f64(&row[4])?,
+            volume: to_f64(&row[5])?,
+            close_time: to_i64(&row[6])?,
+            quote_asset_volume: to_f64(&row[7])?,
+            number_of_trades: to_i64(&row[8])?,
+            taker_buy_base_asset_volume: to_f64(&row[9])?,
+            taker_buy_quote_asset_volume: to_f64(&row[10])?,
+            ignore: row.get(11).and_then(|v| v.as_str()).map(str::to_string),
+        })
+    }
+}
+
+impl TryFrom<Vec<Vec<serde_json::Value>>> for KlineSummaries {
+    type Error = crate::errors::Error;
+
+    fn try_from(rows: Vec<Vec<serde_json::Value>>) -> crate::errors::Result<Self> {
+        let klines = rows
+            .iter()
+            .map(|row| KlineSummary::try_from(row.as_slice()))
+            .collect::<crate::errors::Result<_>>()?;
+        Ok(KlineSummaries::AllKlineSummaries(klines))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PropertyCmd {
     pub id: i32,
@@ -2185,4 +2227,40 @@ mod test {
         let result = serde_json::from_str::<Vec<Filters>>(&fc);
         assert!(result.is_ok(), "{result:?}");
     }
+
+    fn read_klines(name: &str) -> crate::errors::Result<Vec<KlineSummary>> {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("test_data");
+        d.push(name);
+        let rows: Vec<Vec<serde_json::Value>> = serde_json::from_str(&std::fs::read_to_string(d).unwrap()).unwrap();
+        let KlineSummaries::AllKlineSummaries(klines) = KlineSummaries::try_from(rows)?;
+        Ok(klines)
+    }
+
+    #[test]
+    fn klines_parse() {
+        let klines = read_klines("futures_klines.json").unwrap();
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[0].open_time, 1699999200000);
+        assert_eq!(klines[0].close, 36488.2);
+        assert_eq!(klines[0].number_of_trades, 11894);
+        assert_eq!(klines[1].taker_buy_quote_asset_volume, 9308124.0788);
+        assert_eq!(klines[1].ignore.as_deref(), Some("0"));
+
+        let index_klines = read_klines("index_price_klines.json").unwrap();
+        assert_eq!(index_klines[0].high, 36521.10978723);
+        assert_eq!(index_klines[0].volume, 0.0);
+    }
+
+    #[test]
+    fn klines_reject_malformed_rows() {
+        let short = vec![vec![serde_json::json!(1699999200000u64), serde_json::json!("1.0")]];
+        assert!(KlineSummaries::try_from(short).is_err());
+        let mut row: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[1699999200000, "1.0", "1.0", "1.0", "1.0", "0", 1699999259999, "0", 1, "0", "0"]"#,
+        )
+        .unwrap();
+        row[2] = serde_json::json!("not a price");
+        assert!(KlineSummaries::try_from(vec![row]).is_err());
+    }
 }
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hex::encode as hex_encode;
+use ring::hmac;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, RsaKeyPair, RSA_PKCS1_SHA256};
+use url::form_urlencoded::byte_serialize;
+
+use crate::credentials::Credentials;
+use crate::errors::*;
+
+/// Type of the API key, which decides how the secret key is read and how requests are signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyType {
+    /// The secret key is an HMAC-SHA256 secret, signatures are hex encoded
+    #[default]
+    Hmac,
+    /// The secret key is a PEM encoded PKCS#8 Ed25519 private key, signatures are base64 encoded
+    Ed25519,
+    /// The secret key is a PEM encoded PKCS#8 or PKCS#1 RSA private key, signatures are
+    /// RSASSA-PKCS1-v1_5 with SHA-256, base64 encoded
+    Rsa,
+}
+
+/// Signs the query string of requests to `SIGNED` endpoints
+#[derive(Clone)]
+pub enum Signer {
+    Hmac(hmac::Key),
+    Ed25519(Arc<Ed25519KeyPair>),
+    Rsa(Arc<RsaKeyPair>),
+}
+
+impl Signer {
+    /// Read `secret_key` according to the `key_type`
+    pub fn new(key_type: KeyType, secret_key: &str) -> Result<Self> {
+        match key_type {
+            KeyType::Hmac => Ok(Self::hmac(secret_key)),
+            KeyType::Ed25519 => Self::ed25519_from_pem(secret_key),
+            KeyType::Rsa => Self::rsa_from_pem(secret_key),
+        }
+    }
+
+    pub fn hmac(secret_key: &str) -> Self { Signer::Hmac(hmac::Key::new(hmac::HMAC_SHA256, secret_key.as_bytes())) }
+
+    pub fn ed25519_from_pem(pem: &str) -> Result<Self> {
+        let der = decode_pem(pem)?;
+        let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+            .map_err(|e| Error::InvalidSigningKey(format!("ed25519: {e}")))?;
+        Ok(Signer::Ed25519(Arc::new(key_pair)))
+    }
+
+    pub fn rsa_from_pem(pem: &str) -> Result<Self> {
+        let der = decode_pem(pem)?;
+        let key_pair = RsaKeyPair::from_pkcs8(&der)
+            .or_else(|_| RsaKeyPair::from_der(&der))
+            .map_err(|e| Error::InvalidSigningKey(format!("rsa: {e}")))?;
+        Ok(Signer::Rsa(Arc::new(key_pair)))
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            Signer::Hmac(_) => KeyType::Hmac,
+            Signer::Ed25519(_) => KeyType::Ed25519,
+            Signer::Rsa(_) => KeyType::Rsa,
+        }
+    }
+
+    /// Sign `payload`, returning the signature encoded as the exchange expects it in a query string
+    pub fn sign(&self, payload: &str) -> Result<String> {
+        let signature = self.signature(payload)?;
+        match self {
+            Signer::Hmac(_) => Ok(signature),
+            _ => Ok(byte_serialize(signature.as_bytes()).collect()),
+        }
+    }
+
+    /// Sign `payload`, returning the hex or base64 signature without url encoding, as sent in the
+    /// parameters of websocket API requests
+    pub fn signature(&self, payload: &str) -> Result<String> {
+        match self {
+            Signer::Hmac(key) => Ok(hex_encode(hmac::sign(key, payload.as_bytes()).as_ref())),
+            Signer::Ed25519(key_pair) => Ok(BASE64.encode(key_pair.sign(payload.as_bytes()))),
+            Signer::Rsa(key_pair) => {
+                let mut signature = vec![0; key_pair.public().modulus_len()];
+                key_pair
+                    .sign(
+                        &RSA_PKCS1_SHA256,
+                        &SystemRandom::new(),
+                        payload.as_bytes(),
+                        &mut signature,
+                    )
+                    .map_err(|e| Error::InvalidSigningKey(format!("rsa: {e}")))?;
+                Ok(BASE64.encode(signature))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signer").field(&self.key_type()).finish()
+    }
+}
+
+/// Keeps the signer of the last credentials, so that private keys are only parsed again
+/// when the credentials are rotated
+#[derive(Clone)]
+pub(crate) struct SignerCache {
+    key_type: KeyType,
+    last: Arc<Mutex<Option<(Credentials, Signer)>>>,
+}
+
+impl SignerCache {
+    pub(crate) fn new(key_type: KeyType) -> Self {
+        Self {
+            key_type,
+            last: Default::default(),
+        }
+    }
+
+    pub(crate) fn signer(&self, credentials: &Credentials) -> Result<Signer> {
+        let mut last = self.last.lock().unwrap();
+        match last.as_ref() {
+            Some((cached, signer)) if cached == credentials => Ok(signer.clone()),
+            _ => {
+                let signer = Signer::new(self.key_type, credentials.secret_key())?;
+                *last = Some((credentials.clone(), signer.clone()));
+                Ok(signer)
+            }
+        }
+    }
+}
+
+/// Decode the base64 body of a PEM document
+fn decode_pem(pem: &str) -> Result<Vec<u8>> {
+    let body = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+        .collect::<String>();
+    BASE64
+        .decode(body)
+        .map_err(|e| Error::InvalidSigningKey(format!("pem: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn read_key(name: &str) -> String {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("test_data");
+        d.push(name);
+        std::fs::read_to_string(d).unwrap()
+    }
+
+    #[test]
+    fn test_hmac_signature() {
+        // Example from the Binance API documentation
+        let signer = Signer::new(
+            KeyType::Hmac,
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j",
+        )
+        .unwrap();
+        let payload = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        assert_eq!(
+            signer.sign(payload).unwrap(),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[test]
+    fn test_ed25519_signature() {
+        // RFC 8032 test vector 2, the key is its seed wrapped in PKCS#8
+        let signer = Signer::new(KeyType::Ed25519, &read_key("ed25519_private_key.pem")).unwrap();
+        assert_eq!(signer.key_type(), KeyType::Ed25519);
+        let signature = hex::decode(
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        )
+        .unwrap();
+        let expected: String = byte_serialize(BASE64.encode(signature).as_bytes()).collect();
+        assert_eq!(signer.sign("r").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_rsa_signature() {
+        // Signed with `openssl dgst -sha256 -sign test_data/rsa_private_key.pem`
+        let signer = Signer::new(KeyType::Rsa, &read_key("rsa_private_key.pem")).unwrap();
+        assert_eq!(signer.key_type(), KeyType::Rsa);
+        let payload = "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=1&price=0.2&timestamp=1668481559918&recvWindow=5000";
+        let expected = "MjThPNkAvTWpBWD6+zOqo7T34kIG9oNWdx7EfdIycJs1LGenurCr4iFSlwLqJW4bdWrKePlwtCd1nXUcf2d1hO4r0cpcpnTvuybOD7di3DH4lZi0UAES9eOhmgO3m7UkExlZhJ3BNBihEiDPtV+MRHgEGP0Qs/3/RheVR5cpW8ohPO/3lvK88Feot+SVYSSq5CC/6fac5YYODGdQ/X7R98U+uiho3O2m8waMdHl0qd35PsUw3zdmPFAQAbrw7b+eZevfROu+/VoX2VucIgjSqx7L/9ng1nGiexBD+5tDJB6E+mk5HFJo6Y3vpr4sFqcbiVC7TlAJmgWVgLtdFgcRfA==";
+        let signature = signer.sign(payload).unwrap();
+        assert!(!signature.contains(['+', '/', '=']), "{signature}");
+        assert_eq!(signature, byte_serialize(expected.as_bytes()).collect::<String>());
+    }
+
+    #[test]
+    fn test_signer_cache_follows_credentials() {
+        let cache = SignerCache::new(KeyType::Hmac);
+        let first = cache.signer(&Credentials::new("key", "secret1")).unwrap();
+        let again = cache.signer(&Credentials::new("key", "secret1")).unwrap();
+        let rotated = cache.signer(&Credentials::new("key", "secret2")).unwrap();
+        assert_eq!(first.sign("a=1").unwrap(), again.sign("a=1").unwrap());
+        assert_ne!(first.sign("a=1").unwrap(), rotated.sign("a=1").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        assert!(matches!(
+            Signer::new(KeyType::Ed25519, "not a key"),
+            Err(Error::InvalidSigningKey(_))
+        ));
+        assert!(matches!(
+            Signer::new(KeyType::Rsa, &read_key("ed25519_private_key.pem")),
+            Err(Error::InvalidSigningKey(_))
+        ));
+    }
+}
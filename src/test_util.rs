@@ -0,0 +1,48 @@
+//! Local HTTP servers standing in for the exchange in tests.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Answers the requests to `listener` in turn with the status and JSON body `respond` returns for
+/// their first line, `requests` times or forever when `None`. Returns the first lines.
+pub(crate) async fn serve_http<F>(listener: TcpListener, requests: Option<usize>, mut respond: F) -> Vec<String>
+where
+    F: FnMut(&str) -> (&'static str, String),
+{
+    let mut request_lines = vec![];
+    while requests.is_none_or(|requests| request_lines.len() < requests) {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let n = stream.read(&mut request).await.unwrap();
+        let request_line = String::from_utf8_lossy(&request[..n])
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let (status, body) = respond(&request_line);
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        request_lines.push(request_line);
+    }
+    request_lines
+}
+
+/// A local server answering `bodies` in turn with `200 OK`. Returns its url and the task serving
+/// them, which returns the first lines of the requests once every body is sent.
+pub(crate) async fn serve_bodies<B: ToString>(
+    bodies: impl IntoIterator<Item = B>,
+) -> (String, JoinHandle<Vec<String>>) {
+    let bodies: Vec<String> = bodies.into_iter().map(|body| body.to_string()).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = bodies.len();
+    let mut bodies = bodies.into_iter();
+    let server = tokio::spawn(serve_http(listener, Some(requests), move |_| {
+        ("200 OK", bodies.next().unwrap())
+    }));
+    (url, server)
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+
+use crate::errors::*;
+use crate::rest_model::ServerTime;
+use crate::util::replace_timestamp;
+
+/// Offset between the exchange clock and the local clock, as measured by the last sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOffset {
+    /// Milliseconds to add to the local clock to get the server time
+    pub offset_millis: i64,
+    /// Round trip of the server time request the offset was measured with
+    pub round_trip: Duration,
+    /// Local time of the measurement, in milliseconds
+    pub synced_at: u64,
+}
+
+/// Keeps the offset between the local clock and the server time, so that signed requests carry
+/// a timestamp inside of the exchange's `recvWindow` even when the local clock drifts.
+///
+/// Until a first sync completes the local clock is used. Cloning a [`TimeSync`] shares its state,
+/// so every client created from the same [`crate::config::Config`] signs with the same offset.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use binance::{api::*, config::*, general::*};
+/// use std::time::Duration;
+/// let general: General = Binance::new_with_config(None, None, &Config::default());
+/// let sync_task = general.spawn_time_sync(Duration::from_secs(60));
+/// ```
+#[derive(Clone, Default)]
+pub struct TimeSync {
+    offset: Arc<RwLock<Option<TimeOffset>>>,
+}
+
+impl TimeSync {
+    pub fn new() -> Self { Self::default() }
+
+    /// The last measured offset, if any
+    pub fn offset(&self) -> Option<TimeOffset> { *self.offset.read().unwrap() }
+
+    /// The current server time in milliseconds, estimated from the local clock and the last offset
+    pub fn timestamp(&self) -> u64 {
+        let local = now_millis();
+        match self.offset() {
+            Some(offset) => local.saturating_add_signed(offset.offset_millis),
+            None => local,
+        }
+    }
+
+    /// Replace the timestamp of a signed request built from the local clock with the server time.
+    /// The request is left untouched until a first sync completes.
+    pub fn correct_timestamp(&self, request: &str) -> String {
+        match self.offset() {
+            Some(_) => replace_timestamp(request, self.timestamp()),
+            None => request.to_string(),
+        }
+    }
+
+    /// Record a server time measured between the local times `sent_at` and `received_at`.
+    /// The server is assumed to have answered halfway through the round trip.
+    pub fn record(&self, sent_at: u64, server_time: u64, received_at: u64) -> TimeOffset {
+        let received_at = received_at.max(sent_at);
+        let round_trip = received_at - sent_at;
+        let offset = TimeOffset {
+            offset_millis: server_time as i64 - (sent_at + round_trip / 2) as i64,
+            round_trip: Duration::from_millis(round_trip),
+            synced_at: received_at,
+        };
+        *self.offset.write().unwrap() = Some(offset);
+        offset
+    }
+
+    /// Forget the measured offset and fall back to the local clock
+    pub fn reset(&self) { *self.offset.write().unwrap() = None; }
+
+    /// Measure the offset once with `fetch`, usually a `get_server_time` call
+    pub async fn sync<F, Fut>(&self, fetch: F) -> Result<TimeOffset>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ServerTime>>,
+    {
+        let sent_at = now_millis();
+        let server_time = fetch().await?;
+        let received_at = now_millis();
+        Ok(self.record(sent_at, server_time.server_time, received_at))
+    }
+
+    /// Measure the offset with `fetch` every `interval` in a background task.
+    /// Failed measurements keep the previous offset. Abort the returned handle to stop syncing.
+    pub fn spawn<F, Fut>(&self, interval: Duration, mut fetch: F) -> JoinHandle<()>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<ServerTime>> + Send,
+    {
+        let time_sync = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let _ = time_sync.sync(&mut fetch).await;
+            }
+        })
+    }
+}
+
+impl fmt::Debug for TimeSync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimeSync").field("offset", &self.offset()).finish()
+    }
+}
+
+/// Two time syncs are equal when they share the same state
+impl PartialEq for TimeSync {
+    fn eq(&self, other: &Self) -> bool { Arc::ptr_eq(&self.offset, &other.offset) }
+}
+
+impl Eq for TimeSync {}
+
+fn now_millis() -> u64 { Utc::now().timestamp_millis() as u64 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_offset() {
+        let time_sync = TimeSync::new();
+        assert_eq!(time_sync.offset(), None);
+
+        let offset = time_sync.record(1_000, 1_700, 1_200);
+        assert_eq!(offset.offset_millis, 600);
+        assert_eq!(offset.round_trip, Duration::from_millis(200));
+        assert_eq!(time_sync.offset(), Some(offset));
+
+        let offset = time_sync.record(5_000, 4_000, 5_000);
+        assert_eq!(offset.offset_millis, -1_000);
+        assert_eq!(offset.round_trip, Duration::ZERO);
+
+        let shared = time_sync.clone();
+        assert_eq!(shared, time_sync);
+        let timestamp = shared.timestamp();
+        let local = now_millis();
+        assert!(timestamp < local && local - timestamp < 1_100);
+
+        let request = format!("recvWindow=5000&timestamp={local}&symbol=BTCUSDT");
+        let corrected = shared.correct_timestamp(&request);
+        assert_ne!(corrected, request);
+        assert!(corrected.starts_with("recvWindow=5000&timestamp=") && corrected.ends_with("&symbol=BTCUSDT"));
+
+        time_sync.reset();
+        assert_eq!(shared.offset(), None);
+        assert_eq!(shared.correct_timestamp(&request), request);
+        assert_ne!(TimeSync::new(), time_sync);
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let time_sync = TimeSync::new();
+        let server_time = now_millis() + 10_000;
+        let offset = time_sync
+            .sync(|| async { Ok(ServerTime { server_time }) })
+            .await
+            .unwrap();
+        assert!((9_000..=10_000).contains(&offset.offset_millis), "{offset:?}");
+
+        let err = time_sync
+            .sync(|| async { Err(Error::Msg("unreachable".to_string())) })
+            .await;
+        assert!(err.is_err());
+        assert_eq!(time_sync.offset(), Some(offset));
+    }
+}
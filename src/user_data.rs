@@ -0,0 +1,425 @@
+//! User data streams which keep their listen key alive.
+//!
+//! A [`UserDataSession`] starts a listen key, connects its websocket and sends a keepalive every
+//! 30 minutes. When the key expires, be it from a `listenKeyExpired` event or an
+//! [`Error::InvalidListenKey`] on keepalive, or when the websocket ends, it starts a new key and
+//! connects again. Attempts after the first are spaced by the jittered backoff of the config's
+//! [`ReconnectPolicy`], or of the default policy when none is set.
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use tokio::time::{interval_at, Instant, Interval};
+
+use crate::api::Binance;
+use crate::config::Config;
+use crate::errors::*;
+#[cfg(feature = "margin_api")]
+use crate::margin::Margin;
+use crate::websockets::{EventStream, ReconnectPolicy, SocketHandler};
+
+/// Interval of the keepalives, listen keys expire after 60 minutes without one
+pub static KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+/// An event of a user data stream
+pub trait UserDataEvent: serde::de::DeserializeOwned + Send + 'static {
+    /// Whether the event tells that the listen key of the stream expired
+    fn is_listen_key_expired(&self) -> bool;
+}
+
+impl UserDataEvent for crate::ws_model::WebsocketEvent {
+    fn is_listen_key_expired(&self) -> bool { matches!(self, Self::ListenKeyExpired(_)) }
+}
+
+#[cfg(feature = "futures_api")]
+impl UserDataEvent for crate::futures::ws_model::WebsocketEvent {
+    fn is_listen_key_expired(&self) -> bool { matches!(self, Self::ListenKeyExpired(_)) }
+}
+
+impl UserDataEvent for crate::coin_margin::ws_model::UserStreamEvent {
+    fn is_listen_key_expired(&self) -> bool { matches!(self, Self::ListenKeyExpired(_)) }
+}
+
+/// The listen key endpoints of each market
+#[derive(Clone)]
+enum ListenKeys {
+    Spot(crate::userstream::UserStream),
+    #[cfg(feature = "margin_api")]
+    Margin(Margin),
+    #[cfg(feature = "margin_api")]
+    IsolatedMargin(Margin, String),
+    #[cfg(feature = "futures_api")]
+    UsdM(crate::futures::userstream::UserStream),
+    CoinM(crate::coin_margin::userstream::UserStream),
+}
+
+impl ListenKeys {
+    async fn start(&self) -> Result<String> {
+        let stream = match self {
+            ListenKeys::Spot(api) => api.start().await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::Margin(api) => api.start().await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::IsolatedMargin(api, symbol) => api.start_isolated(symbol).await?,
+            #[cfg(feature = "futures_api")]
+            ListenKeys::UsdM(api) => api.start().await?,
+            ListenKeys::CoinM(api) => api.start().await?,
+        };
+        Ok(stream.listen_key)
+    }
+
+    async fn keep_alive(&self, listen_key: &str) -> Result<()> {
+        match self {
+            ListenKeys::Spot(api) => api.keep_alive(listen_key).await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::Margin(api) => api.keep_alive(listen_key).await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::IsolatedMargin(api, symbol) => api.keep_alive_isolated(listen_key, symbol).await?,
+            #[cfg(feature = "futures_api")]
+            ListenKeys::UsdM(api) => api.keep_alive(listen_key).await?,
+            ListenKeys::CoinM(api) => api.keep_alive(listen_key).await?,
+        };
+        Ok(())
+    }
+
+    async fn close(&self, listen_key: &str) -> Result<()> {
+        match self {
+            ListenKeys::Spot(api) => api.close(listen_key).await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::Margin(api) => api.close(listen_key).await?,
+            #[cfg(feature = "margin_api")]
+            ListenKeys::IsolatedMargin(api, symbol) => api.close_isolated(listen_key, symbol).await?,
+            #[cfg(feature = "futures_api")]
+            ListenKeys::UsdM(api) => api.close(listen_key).await?,
+            ListenKeys::CoinM(api) => api.close(listen_key).await?,
+        };
+        Ok(())
+    }
+
+    async fn connect(&self, socket_handler: &mut SocketHandler, listen_key: &str) -> Result<()> {
+        match self {
+            #[cfg(feature = "futures_api")]
+            ListenKeys::UsdM(_) => socket_handler.connect_futures(listen_key).await,
+            ListenKeys::CoinM(_) => socket_handler.connect_coin_m(listen_key).await,
+            _ => socket_handler.connect(listen_key).await,
+        }
+    }
+}
+
+/// The typed events of a user data stream, which obtains a new listen key and reconnects whenever
+/// the current one expires.
+///
+/// Credentials are those of the config's credential provider. Nothing is started until the stream
+/// is polled. Errors are yielded without ending the stream, the next poll tries again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::config::Config;
+/// use binance::user_data::UserDataSession;
+/// use binance::ws_model::WebsocketEvent;
+/// use futures::StreamExt;
+///
+/// # async fn run(config: Config) -> binance::errors::Result<()> {
+/// let mut session = UserDataSession::spot(&config);
+/// while let Some(event) = session.next().await {
+///     if let WebsocketEvent::OrderUpdate(order) = event? {
+///         println!("{:?}", order);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct UserDataSession<WE> {
+    listen_keys: ListenKeys,
+    listen_key: Arc<Mutex<Option<String>>>,
+    state: Option<SessionState<WE>>,
+    events: Option<Pin<Box<dyn Stream<Item = Result<WE>> + Send>>>,
+}
+
+impl UserDataSession<crate::ws_model::WebsocketEvent> {
+    pub fn spot(conf: &Config) -> Self { Self::new(ListenKeys::Spot(Binance::new_with_config(None, None, conf)), conf) }
+
+    #[cfg(feature = "margin_api")]
+    pub fn margin(conf: &Config) -> Self {
+        Self::new(ListenKeys::Margin(Binance::new_with_config(None, None, conf)), conf)
+    }
+
+    #[cfg(feature = "margin_api")]
+    pub fn isolated_margin(symbol: &str, conf: &Config) -> Self {
+        Self::new(
+            ListenKeys::IsolatedMargin(Binance::new_with_config(None, None, conf), symbol.to_string()),
+            conf,
+        )
+    }
+}
+
+#[cfg(feature = "futures_api")]
+impl UserDataSession<crate::futures::ws_model::WebsocketEvent> {
+    pub fn usd_m(conf: &Config) -> Self {
+        Self::new(ListenKeys::UsdM(Binance::new_with_config(None, None, conf)), conf)
+    }
+}
+
+impl UserDataSession<crate::coin_margin::ws_model::UserStreamEvent> {
+    pub fn coin_m(conf: &Config) -> Self {
+        Self::new(ListenKeys::CoinM(Binance::new_with_config(None, None, conf)), conf)
+    }
+}
+
+impl<WE: UserDataEvent> UserDataSession<WE> {
+    fn new(listen_keys: ListenKeys, conf: &Config) -> Self {
+        let listen_key = Arc::new(Mutex::new(None));
+        UserDataSession {
+            state: Some(SessionState {
+                conf: conf.clone(),
+                listen_keys: listen_keys.clone(),
+                listen_key: listen_key.clone(),
+                events: None,
+                keep_alive: None,
+                keep_alive_interval: KEEP_ALIVE_INTERVAL,
+                reconnect_policy: conf.reconnect_policy.clone().unwrap_or_default(),
+                connect_attempts: 0,
+            }),
+            listen_keys,
+            listen_key,
+            events: None,
+        }
+    }
+
+    /// Sets the interval of the keepalives, [`KEEP_ALIVE_INTERVAL`] by default
+    pub fn set_keep_alive_interval(mut self, interval: Duration) -> Self {
+        if let Some(state) = self.state.as_mut() {
+            state.keep_alive_interval = interval;
+        }
+        self
+    }
+
+    /// The listen key in use, if started
+    pub fn listen_key(&self) -> Option<String> { self.listen_key.lock().unwrap_or_else(|e| e.into_inner()).clone() }
+
+    /// Close the websocket and invalidate the listen key
+    pub async fn close(self) -> Result<()> {
+        let listen_key = self.listen_key();
+        drop(self.events);
+        match listen_key {
+            Some(listen_key) => self.listen_keys.close(&listen_key).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl<WE: UserDataEvent> Stream for UserDataSession<WE> {
+    type Item = Result<WE>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(state) = self.state.take() {
+            let events = futures::stream::unfold(state, |mut state| async move {
+                let event = state.next().await;
+                Some((event, state))
+            });
+            self.events = Some(Box::pin(events));
+        }
+        match self.events.as_mut() {
+            Some(events) => events.as_mut().poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+fn new_interval(period: Duration) -> Interval { interval_at(Instant::now() + period, period) }
+
+struct SessionState<WE> {
+    conf: Config,
+    listen_keys: ListenKeys,
+    listen_key: Arc<Mutex<Option<String>>>,
+    events: Option<EventStream<WE>>,
+    /// Started on connection
+    keep_alive: Option<Interval>,
+    keep_alive_interval: Duration,
+    reconnect_policy: ReconnectPolicy,
+    /// Connections made since the last event, reset when an event is received
+    connect_attempts: u32,
+}
+
+impl<WE: UserDataEvent> SessionState<WE> {
+    async fn next(&mut self) -> Result<WE> {
+        loop {
+            let (Some(events), Some(keep_alive)) = (self.events.as_mut(), self.keep_alive.as_mut()) else {
+                if let Some(attempt) = self.connect_attempts.checked_sub(1) {
+                    tokio::time::sleep(self.reconnect_policy.backoff(attempt)).await;
+                }
+                self.connect_attempts = self.connect_attempts.saturating_add(1);
+                self.connect().await?;
+                continue;
+            };
+            let event = tokio::select! {
+                _ = keep_alive.tick() => None,
+                event = events.next() => Some(event),
+            };
+            match event {
+                None => {
+                    let Some(listen_key) = self.current_listen_key() else {
+                        continue;
+                    };
+                    match self.listen_keys.keep_alive(&listen_key).await {
+                        Ok(()) => {}
+                        Err(Error::InvalidListenKey(_)) => self.expire(),
+                        Err(e) => return Err(e),
+                    }
+                }
+                Some(Some(Ok(event))) => {
+                    self.connect_attempts = 0;
+                    if event.is_listen_key_expired() {
+                        self.expire();
+                    }
+                    return Ok(event);
+                }
+                Some(Some(Err(e))) => return Err(e),
+                // The websocket ended, start a new key and connect again
+                Some(None) => self.events = None,
+            }
+        }
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let listen_key = self.listen_keys.start().await?;
+        let mut socket_handler = SocketHandler::new_with_options(self.conf.clone());
+        self.listen_keys.connect(&mut socket_handler, &listen_key).await?;
+        self.events = Some(socket_handler.into_stream());
+        self.keep_alive = Some(new_interval(self.keep_alive_interval));
+        *self.listen_key.lock().unwrap_or_else(|e| e.into_inner()) = Some(listen_key);
+        Ok(())
+    }
+
+    fn current_listen_key(&self) -> Option<String> { self.listen_key.lock().unwrap_or_else(|e| e.into_inner()).clone() }
+
+    /// Forget the listen key, a new one is started on the next event
+    fn expire(&mut self) {
+        self.events = None;
+        *self.listen_key.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::Credentials;
+    use crate::test_util::serve_http;
+    use crate::ws_model::WebsocketEvent;
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Starts a new listen key on each `POST` and rejects every keepalive as an unknown listen key
+    async fn serve_listen_keys(listener: TcpListener, requests: Arc<Mutex<Vec<String>>>) {
+        let mut keys = 0;
+        serve_http(listener, None, move |line| {
+            requests
+                .lock()
+                .unwrap()
+                .push(line.split(' ').take(2).collect::<Vec<_>>().join(" "));
+            if line.starts_with("POST") {
+                keys += 1;
+                ("200 OK", format!(r#"{{"listenKey":"k{keys}"}}"#))
+            } else {
+                (
+                    "400 Bad Request",
+                    r#"{"code":-1125,"msg":"This listenKey does not exist."}"#.to_string(),
+                )
+            }
+        })
+        .await;
+    }
+
+    /// The first stream expires its listen key, the second sends nothing and the third one event
+    async fn serve_streams(listener: TcpListener) {
+        let balance_update = r#"{"e":"balanceUpdate","E":1,"a":"BTC","d":"1.5","T":2}"#;
+        let expired = r#"{"e":"listenKeyExpired","E":3,"listenKey":"k1"}"#;
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut path = String::new();
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                    path = request.uri().path().to_string();
+                    Ok(response)
+                })
+                .await
+                .unwrap();
+                let messages = match path.as_str() {
+                    "/ws/k1" => vec![balance_update, expired],
+                    "/ws/k3" => vec![balance_update],
+                    _ => vec![],
+                };
+                for message in messages {
+                    socket.send(Message::Text(message.to_string())).await.unwrap();
+                }
+                while let Some(Ok(_)) = socket.next().await {}
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn test_renews_listen_key() {
+        let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default()
+            .set_rest_api_endpoint(format!("http://{}", http.local_addr().unwrap()))
+            .set_ws_endpoint(format!("ws://{}", ws.local_addr().unwrap()))
+            .set_credential_provider(Credentials::new("key", "secret"));
+        let requests = Arc::new(Mutex::new(vec![]));
+        tokio::spawn(serve_listen_keys(http, requests.clone()));
+        tokio::spawn(serve_streams(ws));
+
+        let mut session = UserDataSession::spot(&config).set_keep_alive_interval(Duration::from_millis(200));
+        let mut events = vec![];
+        for _ in 0..3 {
+            let event = tokio::time::timeout(Duration::from_secs(5), session.next())
+                .await
+                .unwrap();
+            events.push(event.unwrap().unwrap());
+        }
+        assert!(matches!(events[0], WebsocketEvent::BalanceUpdate(_)));
+        assert!(matches!(events[1], WebsocketEvent::ListenKeyExpired(_)));
+        // The keepalive of the second key fails, the third key is started
+        assert!(matches!(events[2], WebsocketEvent::BalanceUpdate(_)));
+        assert_eq!(session.listen_key().as_deref(), Some("k3"));
+        assert_eq!(*requests.lock().unwrap(), vec![
+            "POST /api/v3/userDataStream",
+            "POST /api/v3/userDataStream",
+            "PUT /api/v3/userDataStream?listenKey=k2",
+            "POST /api/v3/userDataStream"
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_backs_off_between_failed_starts() {
+        let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default()
+            .set_rest_api_endpoint(format!("http://{}", http.local_addr().unwrap()))
+            .set_credential_provider(Credentials::new("key", "secret"))
+            .set_reconnect_policy(ReconnectPolicy {
+                max_attempts: None,
+                initial_backoff: Duration::from_millis(200),
+                max_backoff: Duration::from_millis(200),
+            });
+        let body = r#"{"code":-1000,"msg":"An unknown error occured while processing the request."}"#;
+        tokio::spawn(serve_http(http, None, move |_| ("400 Bad Request", body.to_string())));
+
+        let mut session = UserDataSession::spot(&config);
+        let started = Instant::now();
+        for _ in 0..3 {
+            assert!(session.next().await.unwrap().is_err());
+        }
+        // The first attempt is immediate, the two next ones wait at least half of the backoff
+        assert!(
+            started.elapsed() >= Duration::from_millis(200),
+            "{:?}",
+            started.elapsed()
+        );
+    }
+}
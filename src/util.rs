@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::ops::Not;
 
 use boolinator::Boolinator;
@@ -22,6 +23,17 @@ where
     Ok(qs::to_string(&payload)?)
 }
 
+/// The parameters of `payload` as sent in a query string, sorted by name
+pub fn build_params_p<S>(payload: S) -> Result<BTreeMap<String, String>>
+where
+    S: serde::Serialize,
+{
+    let query_string = qs::to_string(&payload)?;
+    Ok(url::form_urlencoded::parse(query_string.as_bytes())
+        .into_owned()
+        .collect())
+}
+
 pub fn build_signed_request(
     parameters: impl IntoIterator<Item = (impl AsRef<str>, impl AsRef<str>)>,
     recv_window: u64,
@@ -71,14 +83,39 @@ where
     Ok(request)
 }
 
-pub fn to_i64(v: &Value) -> i64 {
-    // TODO: should this return result?
-    v.as_i64().unwrap()
+/// Replaces the timestamp of an already built signed request with the current time
+pub(crate) fn refresh_timestamp(request: &str) -> Result<String> { Ok(replace_timestamp(request, get_timestamp()?)) }
+
+/// Replaces the timestamp of an already built signed request
+pub(crate) fn replace_timestamp(request: &str, timestamp: u64) -> String {
+    request
+        .split('&')
+        .map(|kv| {
+            if kv.starts_with("timestamp=") {
+                format!("timestamp={timestamp}")
+            } else {
+                kv.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("&")
 }
 
-pub fn to_f64(v: &Value) -> f64 {
-    // TODO: should this return result?
-    v.as_str().unwrap().parse().unwrap()
+/// An integer sent as a JSON number or string
+pub fn to_i64(v: &Value) -> Result<i64> {
+    v.as_i64()
+        .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+        .ok_or_else(|| Error::Msg(format!("expected an integer, got {v}")))
+}
+
+/// A decimal sent as a JSON string or number
+pub fn to_f64(v: &Value) -> Result<f64> {
+    match v.as_str() {
+        Some(s) => Ok(s.parse()?),
+        None => v
+            .as_f64()
+            .ok_or_else(|| Error::Msg(format!("expected a decimal, got {v}"))),
+    }
 }
 
 pub fn get_timestamp() -> Result<u64> { Ok(Utc::now().timestamp_millis() as u64) }
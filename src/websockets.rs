@@ -1,8 +1,15 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use futures::StreamExt;
+use chrono::Utc;
+use futures::{SinkExt, Stream, StreamExt};
+use ring::rand::{SecureRandom, SystemRandom};
 use serde_json::from_str;
 use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
 use tokio_tungstenite::tungstenite::handshake::client::Response;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::WebSocketStream;
@@ -11,6 +18,7 @@ use url::Url;
 
 use crate::config::Config;
 use crate::errors::*;
+use crate::ws_model::QueryResult;
 
 pub static STREAM_ENDPOINT: &str = "stream";
 pub static WS_ENDPOINT: &str = "ws";
@@ -63,18 +71,248 @@ pub fn diff_book_depth_stream(symbol: &str, update_speed: u16) -> String { forma
 
 fn combined_stream(streams: Vec<String>) -> String { streams.join("/") }
 
+/// The stream name of a websocket url, used to label traces and metrics
+pub(crate) fn stream_name(url: &Url) -> String {
+    match url.query() {
+        Some(query) => query.trim_start_matches("streams=").to_string(),
+        None => url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Parse a message of `stream`, reporting it when tracing or metrics are enabled
+#[cfg_attr(not(any(feature = "tracing", feature = "metrics")), allow(unused_variables))]
+pub(crate) fn parse_event<WE: serde::de::DeserializeOwned>(stream: &str, msg: &str) -> Result<WE> {
+    #[cfg(feature = "metrics")]
+    crate::metrics::record_ws_message(stream, msg);
+    let event = from_str(msg);
+    #[cfg(feature = "tracing")]
+    if let Err(e) = &event {
+        tracing::warn!(error = %e, stream, message = msg, "failed to parse websocket message");
+    }
+    #[cfg(feature = "metrics")]
+    if event.is_err() {
+        crate::metrics::record_ws_parse_failure(stream, msg);
+    }
+    Ok(event?)
+}
+
+/// How websockets connect again after they are disconnected, see
+/// [`crate::config::Config::set_reconnect_policy`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Attempts before giving up, `None` to try forever
+    pub max_attempts: Option<u32>,
+    /// Wait before the first attempt, doubled on each failed attempt
+    pub initial_backoff: Duration,
+    /// Longest wait between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Wait before the attempt number `attempt`, starting at 0. Half of the exponential backoff is
+    /// randomized, so that many sockets dropped at once do not reconnect at once.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        let mut bytes = [0; 4];
+        let random = match SystemRandom::new().fill(&mut bytes) {
+            Ok(()) => f64::from(u32::from_le_bytes(bytes)) / f64::from(u32::MAX),
+            Err(_) => 1.0,
+        };
+        backoff / 2 + backoff.mul_f64(random / 2.0)
+    }
+}
+
+/// Events missed while a websocket was disconnected, timestamps are in milliseconds.
+/// Use them to fetch the missed trades, klines or order updates from the REST API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub disconnected_at: u64,
+    pub reconnected_at: u64,
+}
+
+/// Changes of the connection, sent to the connection handler of a websocket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The connection was lost and a reconnection started
+    Disconnected { reason: String },
+    /// The websocket is connected again to the same streams
+    Reconnected { attempts: u32, gap: Gap },
+}
+
+pub(crate) type ConnectionHandler<'a> = Box<dyn FnMut(ConnectionEvent) -> Result<()> + 'a + Send>;
+
+/// Capacity of the queue of messages waiting to be sent by a [`WebSocketSender`]
+static OUTGOING_CAPACITY: usize = 64;
+/// Limit of messages sent to the server per second, above which it drops the connection
+static MAX_MESSAGES_PER_SECOND: usize = 5;
+
+const SUBSCRIBE: &str = "SUBSCRIBE";
+const UNSUBSCRIBE: &str = "UNSUBSCRIBE";
+const LIST_SUBSCRIPTIONS: &str = "LIST_SUBSCRIPTIONS";
+
+/// A message queued by a [`WebSocketSender`]
+#[derive(Debug)]
+enum Outgoing {
+    Message(Message),
+    Request {
+        method: &'static str,
+        params: Vec<String>,
+        reply: oneshot::Sender<Result<QueryResult>>,
+    },
+}
+
+#[derive(Debug)]
+struct PendingRequest {
+    method: &'static str,
+    params: Vec<String>,
+    reply: Option<oneshot::Sender<Result<QueryResult>>>,
+}
+
+/// Requests sent with an id, waiting for the reply of the same id
+#[derive(Debug, Default)]
+struct Requests {
+    next_id: i64,
+    pending: HashMap<i64, PendingRequest>,
+}
+
+impl Requests {
+    fn message(
+        &mut self,
+        method: &'static str,
+        params: Vec<String>,
+        reply: Option<oneshot::Sender<Result<QueryResult>>>,
+    ) -> Message {
+        self.next_id += 1;
+        let request = serde_json::json!({ "method": method, "params": params, "id": self.next_id });
+        self.pending
+            .insert(self.next_id, PendingRequest { method, params, reply });
+        Message::Text(request.to_string())
+    }
+
+    /// Complete the request `msg` replies to, if it is a reply, keeping `subscriptions` up to date
+    fn handle_reply(&mut self, msg: &str, subscriptions: &mut BTreeSet<String>) -> bool {
+        if self.pending.is_empty() {
+            return false;
+        }
+        let Ok(result) = from_str::<QueryResult>(msg) else {
+            return false;
+        };
+        let Some(request) = self.pending.remove(&result.id) else {
+            return false;
+        };
+        let result = match result.error {
+            Some(error) => Err(Error::BinanceError {
+                response: BinanceContentError::new(error.code, error.msg),
+            }),
+            None => {
+                match request.method {
+                    SUBSCRIBE => subscriptions.extend(request.params),
+                    UNSUBSCRIBE => request.params.iter().for_each(|stream| {
+                        subscriptions.remove(stream);
+                    }),
+                    _ => {}
+                }
+                Ok(result)
+            }
+        };
+        if let Some(reply) = request.reply {
+            let _ = reply.send(result);
+        }
+        true
+    }
+}
+
+/// Waits before sending a message which would exceed [`MAX_MESSAGES_PER_SECOND`]
+#[derive(Debug, Default)]
+struct Throttle {
+    sent_at: VecDeque<Instant>,
+}
+
+impl Throttle {
+    async fn wait(&mut self) {
+        if self.sent_at.len() >= MAX_MESSAGES_PER_SECOND {
+            if let Some(oldest) = self.sent_at.pop_front() {
+                tokio::time::sleep_until((oldest + Duration::from_secs(1)).into()).await;
+            }
+        }
+        self.sent_at.push_back(Instant::now());
+    }
+}
+
+/// The streams of a websocket url, from its `streams` query or its path
+fn url_streams(url: &Url) -> BTreeSet<String> {
+    match url.query() {
+        Some(_) => stream_name(url).split('/').map(str::to_string).collect(),
+        None => url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .filter(|stream| *stream != WS_ENDPOINT)
+            .map(str::to_string)
+            .into_iter()
+            .collect(),
+    }
+}
+
 pub struct SocketHandler {
     pub socket: Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)>,
     conf: Config,
+    pub(crate) stream: String,
+    url: Option<Url>,
+    outgoing: Option<(mpsc::Sender<Outgoing>, mpsc::Receiver<Outgoing>)>,
+    closing: bool,
+    subscriptions: BTreeSet<String>,
+    requests: Requests,
+    /// `None` once disabled by [`SocketHandler::set_throttled`]
+    throttle: Option<Throttle>,
 }
 
 impl SocketHandler {
     pub fn new() -> SocketHandler { Self::new_with_options(Config::default()) }
 
-    pub fn new_with_options(conf: Config) -> SocketHandler { SocketHandler { socket: None, conf } }
+    pub fn new_with_options(conf: Config) -> SocketHandler {
+        SocketHandler {
+            socket: None,
+            conf,
+            stream: String::new(),
+            url: None,
+            outgoing: None,
+            closing: false,
+            subscriptions: BTreeSet::new(),
+            requests: Requests::default(),
+            throttle: Some(Throttle::default()),
+        }
+    }
 
     pub async fn connect_multiple(&mut self, endpoints: Vec<String>) -> Result<()> {
-        let mut url = Url::parse(&self.conf.ws_endpoint)?;
+        let ws_endpoint = self.conf.ws_endpoint.clone();
+        self.connect_combined(&ws_endpoint, endpoints).await
+    }
+
+    /// Connect to multiple futures websocket endpoints
+    pub async fn connect_multiple_futures(&mut self, endpoints: Vec<String>) -> Result<()> {
+        let ws_endpoint = self.conf.futures_ws_endpoint.clone();
+        self.connect_combined(&ws_endpoint, endpoints).await
+    }
+
+    async fn connect_combined(&mut self, ws_endpoint: &str, endpoints: Vec<String>) -> Result<()> {
+        let mut url = Url::parse(ws_endpoint)?;
         url.path_segments_mut()
             .map_err(|_| Error::UrlParserError(url::ParseError::RelativeUrlWithoutBase))?
             .push(STREAM_ENDPOINT);
@@ -99,19 +337,56 @@ impl SocketHandler {
         self.handle_connect(url).await
     }
 
+    /// Connect to multiple coin-M futures websocket endpoints
+    pub async fn connect_multiple_coin_m(&mut self, endpoints: Vec<String>) -> Result<()> {
+        let ws_endpoint = self.conf.coinm_ws_endpoint.clone();
+        self.connect_combined(&ws_endpoint, endpoints).await
+    }
+
+    /// Connect to a coin-M futures websocket endpoint
+    pub async fn connect_coin_m(&mut self, endpoint: &str) -> Result<()> {
+        let wss: String = format!("{}/{}/{}", self.conf.coinm_ws_endpoint, WS_ENDPOINT, endpoint);
+        let url = Url::parse(&wss)?;
+
+        self.handle_connect(url).await
+    }
+
     pub async fn handle_connect(&mut self, url: Url) -> Result<()> {
-        match connect_async(url).await {
+        self.subscriptions = url_streams(&url);
+        self.requests.pending.clear();
+        self.open(url).await
+    }
+
+    async fn open(&mut self, url: Url) -> Result<()> {
+        self.stream = stream_name(&url);
+        self.url = Some(url.clone());
+        self.closing = false;
+        #[cfg(feature = "metrics")]
+        if self.socket.is_some() {
+            crate::metrics::record_ws_reconnect(&self.stream);
+        }
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!("binance.ws.connect", %url, reconnect = self.socket.is_some());
+        let connect = connect_async(url);
+        #[cfg(feature = "tracing")]
+        let connect = tracing::Instrument::instrument(connect, span.clone());
+        match connect.await {
             Ok(answer) => {
                 self.socket = Some(answer);
                 Ok(())
             }
-            Err(e) => Err(Error::Msg(format!("Error during handshake {e}"))),
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                span.in_scope(|| tracing::warn!(error = %e, "websocket handshake failed"));
+                Err(Error::Msg(format!("Error during handshake {e}")))
+            }
         }
     }
 
     /// Disconnect from the endpoint
     pub async fn disconnect(&mut self) -> Result<()> {
         if let Some(ref mut socket) = self.socket {
+            self.closing = true;
             socket.0.close(None).await?;
             Ok(())
         } else {
@@ -119,12 +394,315 @@ impl SocketHandler {
         }
     }
 
+    /// Whether messages are held back to stay within [`MAX_MESSAGES_PER_SECOND`], the limit of the
+    /// market streams, which is the default. The websocket API limits requests by weight instead.
+    pub fn set_throttled(&mut self, throttled: bool) { self.throttle = throttled.then(Throttle::default); }
+
     pub fn socket(&self) -> &Option<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response)> { &self.socket }
+
+    /// A sender of messages to the socket, such as subscription requests. Messages are sent while
+    /// the event loop or the [`EventStream`] of this handler is waiting for the next message.
+    pub fn sender(&mut self) -> WebSocketSender {
+        let (tx, _) = self.outgoing.get_or_insert_with(|| mpsc::channel(OUTGOING_CAPACITY));
+        WebSocketSender { tx: tx.clone() }
+    }
+
+    /// The streams currently subscribed to, including those added or removed by a [`WebSocketSender`]
+    pub fn subscriptions(&self) -> impl Iterator<Item = &str> { self.subscriptions.iter().map(String::as_str) }
+
+    /// Subscribe again to the streams added since the connection to `url`, and unsubscribe from
+    /// the removed ones
+    async fn restore_subscriptions(&mut self, url: &Url) -> Result<()> {
+        let initial = url_streams(url);
+        let added: Vec<String> = self.subscriptions.difference(&initial).cloned().collect();
+        let removed: Vec<String> = initial.difference(&self.subscriptions).cloned().collect();
+        for (method, params) in [(SUBSCRIBE, added), (UNSUBSCRIBE, removed)] {
+            if params.is_empty() {
+                continue;
+            }
+            let message = self.requests.message(method, params, None);
+            if let Some((ref mut socket, _)) = self.socket {
+                if let Some(ref mut throttle) = self.throttle {
+                    throttle.wait().await;
+                }
+                socket.send(message).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A [`Stream`] of the events of the connected socket
+    pub fn into_stream<WE>(self) -> EventStream<WE>
+    where
+        WE: serde::de::DeserializeOwned + Send + 'static,
+    {
+        EventStream::new(self)
+    }
+
+    /// The next message of the socket, sending the messages queued by its [`WebSocketSender`]
+    /// meanwhile.
+    ///
+    /// When the connection is lost and a [`ReconnectPolicy`] is configured, connects again to the
+    /// same streams, notifying `on_connection`. Returns `None` if `running` is cleared meanwhile
+    /// or once the socket is closed by [`SocketHandler::disconnect`] or a close message.
+    pub(crate) async fn next_message(
+        &mut self,
+        running: &AtomicBool,
+        on_connection: &mut Option<ConnectionHandler<'_>>,
+    ) -> Result<Option<Message>> {
+        loop {
+            let Some((ref mut socket, _)) = self.socket else {
+                return Err(Error::Msg("Not connected".to_string()));
+            };
+            let received = match self.outgoing {
+                Some((_, ref mut outgoing)) => tokio::select! {
+                    received = socket.next() => received,
+                    Some(outgoing) = outgoing.recv() => {
+                        let message = match outgoing {
+                            Outgoing::Message(message) => message,
+                            Outgoing::Request { method, params, reply } => {
+                                self.requests.message(method, params, Some(reply))
+                            }
+                        };
+                        self.closing |= matches!(message, Message::Close(_));
+                        if let Some(ref mut throttle) = self.throttle {
+                            throttle.wait().await;
+                        }
+                        match socket.send(message).await {
+                            Ok(()) => continue,
+                            Err(e) => Some(Err(e)),
+                        }
+                    }
+                },
+                None => socket.next().await,
+            };
+            let disconnected = match received {
+                Some(Ok(Message::Text(msg))) if self.requests.handle_reply(&msg, &mut self.subscriptions) => continue,
+                Some(Ok(Message::Close(e))) => Error::Msg(format!("Disconnected {e:?}")),
+                Some(Ok(message)) => return Ok(Some(message)),
+                Some(Err(e)) => Error::from(e),
+                None => Error::Msg("Disconnected, the stream ended".to_string()),
+            };
+            if self.closing {
+                return Ok(None);
+            }
+            let (Some(policy), Some(url)) = (self.conf.reconnect_policy.clone(), self.url.clone()) else {
+                return Err(disconnected);
+            };
+
+            // Replies to the requests sent on the lost connection will never come
+            self.requests.pending.clear();
+            let disconnected_at = Utc::now().timestamp_millis() as u64;
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %disconnected, stream = %self.stream, "websocket disconnected");
+            if let Some(handler) = on_connection {
+                handler(ConnectionEvent::Disconnected {
+                    reason: disconnected.to_string(),
+                })?;
+            }
+            let mut attempts = 0;
+            loop {
+                if !running.load(Ordering::Relaxed) {
+                    return Ok(None);
+                }
+                if policy.max_attempts.is_some_and(|max| attempts >= max) {
+                    return Err(Error::Msg(format!(
+                        "{disconnected}, gave up reconnecting after {attempts} attempts"
+                    )));
+                }
+                tokio::time::sleep(policy.backoff(attempts)).await;
+                attempts += 1;
+                if self.open(url.clone()).await.is_ok() {
+                    break;
+                }
+            }
+            // A failure to send shows up as a disconnection on the next read
+            let _ = self.restore_subscriptions(&url).await;
+            if let Some(handler) = on_connection {
+                handler(ConnectionEvent::Reconnected {
+                    attempts,
+                    gap: Gap {
+                        disconnected_at,
+                        reconnected_at: Utc::now().timestamp_millis() as u64,
+                    },
+                })?;
+            }
+        }
+    }
+}
+
+/// Queues messages to send to a websocket, see [`SocketHandler::sender`]. Clones send to the same
+/// socket, and keep doing so after it reconnects.
+#[derive(Debug, Clone)]
+pub struct WebSocketSender {
+    tx: mpsc::Sender<Outgoing>,
+}
+
+impl WebSocketSender {
+    /// Queue `message`, waiting while the queue is full
+    pub async fn send(&self, message: Message) -> Result<()> { self.queue(Outgoing::Message(message)).await }
+
+    pub async fn send_text(&self, text: impl Into<String>) -> Result<()> { self.send(Message::Text(text.into())).await }
+
+    /// Close the websocket, ending its event loop or stream instead of reconnecting
+    pub async fn close(&self) -> Result<()> { self.send(Message::Close(None)).await }
+
+    /// Subscribe to `streams` on the open connection. They are subscribed to again when the
+    /// websocket reconnects.
+    ///
+    /// Waits for the reply of the server, which is only read while the event loop or the stream
+    /// of the websocket is running.
+    pub async fn subscribe(&self, streams: Vec<String>) -> Result<()> {
+        self.request(SUBSCRIBE, streams).await.map(|_| ())
+    }
+
+    /// Unsubscribe from `streams` on the open connection, see [`WebSocketSender::subscribe`]
+    pub async fn unsubscribe(&self, streams: Vec<String>) -> Result<()> {
+        self.request(UNSUBSCRIBE, streams).await.map(|_| ())
+    }
+
+    /// The streams the server has subscribed the connection to, see [`WebSocketSender::subscribe`]
+    pub async fn list_subscriptions(&self) -> Result<Vec<String>> {
+        let result = self.request(LIST_SUBSCRIPTIONS, vec![]).await?;
+        Ok(serde_json::from_value(result.result.unwrap_or_default())?)
+    }
+
+    async fn request(&self, method: &'static str, params: Vec<String>) -> Result<QueryResult> {
+        let (reply, response) = oneshot::channel();
+        self.queue(Outgoing::Request { method, params, reply }).await?;
+        response
+            .await
+            .map_err(|_| Error::Msg(format!("The websocket disconnected before replying to {method}")))?
+    }
+
+    async fn queue(&self, outgoing: Outgoing) -> Result<()> {
+        self.tx
+            .send(outgoing)
+            .await
+            .map_err(|_| Error::Msg("The websocket is closed".to_string()))
+    }
+}
+
+struct EventStreamState {
+    socket_handler: SocketHandler,
+    connection_handler: Option<ConnectionHandler<'static>>,
+    running: AtomicBool,
+}
+
+/// The events of a websocket as a [`Stream`], an alternative to the callbacks of [`WebSockets`].
+///
+/// Messages are only read while the stream is polled, so a slow consumer slows down the socket
+/// instead of buffering events. The stream ends when the socket is closed, after yielding the
+/// error which disconnected it when it does not reconnect.
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::websockets::{agg_trade_stream, SocketHandler};
+/// use binance::ws_model::WebsocketEvent;
+/// use futures::StreamExt;
+///
+/// # async fn run() -> binance::errors::Result<()> {
+/// let mut socket_handler = SocketHandler::new();
+/// socket_handler.connect(&agg_trade_stream("btcusdt")).await?;
+/// let (sender, events) = socket_handler.into_stream::<WebsocketEvent>().split();
+/// let mut trades = std::pin::pin!(events.filter_map(|event| async move {
+///     match event {
+///         Ok(WebsocketEvent::AggTrade(trade)) => Some(trade),
+///         _ => None,
+///     }
+/// }));
+/// while let Some(trade) = trades.next().await {
+///     println!("{trade:?}");
+/// }
+/// sender.close().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct EventStream<WE> {
+    state: Option<EventStreamState>,
+    events: Option<Pin<Box<dyn Stream<Item = Result<WE>> + Send>>>,
+    sender: WebSocketSender,
+}
+
+impl<WE> EventStream<WE>
+where
+    WE: serde::de::DeserializeOwned + Send + 'static,
+{
+    pub fn new(mut socket_handler: SocketHandler) -> Self {
+        let sender = socket_handler.sender();
+        EventStream {
+            state: Some(EventStreamState {
+                socket_handler,
+                connection_handler: None,
+                running: AtomicBool::new(true),
+            }),
+            events: None,
+            sender,
+        }
+    }
+
+    /// Sets the handler notified when the websocket disconnects and reconnects, see
+    /// [`crate::config::Config::set_reconnect_policy`]
+    pub fn set_connection_handler<Callback>(mut self, handler: Callback) -> Self
+    where
+        Callback: FnMut(ConnectionEvent) -> Result<()> + 'static + Send,
+    {
+        if let Some(state) = self.state.as_mut() {
+            state.connection_handler = Some(Box::new(handler));
+        }
+        self
+    }
+
+    pub fn sender(&self) -> WebSocketSender { self.sender.clone() }
+
+    /// Split into a sender of messages and the stream of events
+    pub fn split(self) -> (WebSocketSender, Self) { (self.sender(), self) }
+
+    fn events(state: EventStreamState) -> impl Stream<Item = Result<WE>> + Send {
+        futures::stream::unfold(Some(state), |state| async move {
+            let mut state = state?;
+            loop {
+                let message = state
+                    .socket_handler
+                    .next_message(&state.running, &mut state.connection_handler)
+                    .await;
+                match message {
+                    Ok(Some(Message::Text(msg))) if msg.is_empty() => return None,
+                    Ok(Some(Message::Text(msg))) => {
+                        let event = parse_event(&state.socket_handler.stream, &msg);
+                        return Some((event, Some(state)));
+                    }
+                    Ok(Some(_)) => {}
+                    Ok(None) => return None,
+                    Err(e) => return Some((Err(e), None)),
+                }
+            }
+        })
+    }
+}
+
+impl<WE> Stream for EventStream<WE>
+where
+    WE: serde::de::DeserializeOwned + Send + 'static,
+{
+    type Item = Result<WE>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(state) = self.state.take() {
+            self.events = Some(Box::pin(Self::events(state)));
+        }
+        match self.events.as_mut() {
+            Some(events) => events.as_mut().poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
 }
 
 pub struct WebSockets<'a, WE> {
     pub socket_handler: SocketHandler,
     handler: Box<dyn FnMut(WE) -> Result<()> + 'a + Send>,
+    connection_handler: Option<ConnectionHandler<'a>>,
 }
 
 impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
@@ -143,9 +721,20 @@ impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
         WebSockets {
             socket_handler,
             handler: Box::new(handler),
+            connection_handler: None,
         }
     }
 
+    /// Sets the handler notified when the websocket disconnects and reconnects, see
+    /// [`crate::config::Config::set_reconnect_policy`]
+    pub fn set_connection_handler<Callback>(mut self, handler: Callback) -> Self
+    where
+        Callback: FnMut(ConnectionEvent) -> Result<()> + 'a + Send,
+    {
+        self.connection_handler = Some(Box::new(handler));
+        self
+    }
+
     pub async fn connect_multiple(&mut self, endpoints: Vec<String>) -> Result<()> {
         self.socket_handler.connect_multiple(endpoints).await
     }
@@ -160,28 +749,27 @@ impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
 
     pub async fn handle_connect(&mut self, url: Url) -> Result<()> { self.socket_handler.handle_connect(url).await }
 
+    /// A sender to subscribe to streams or unsubscribe from them while the event loop runs
+    pub fn sender(&mut self) -> WebSocketSender { self.socket_handler.sender() }
+
     /// Disconnect from the endpoint
     pub async fn disconnect(&mut self) -> Result<()> { self.socket_handler.disconnect().await }
 
     pub async fn event_loop(&mut self, running: &AtomicBool) -> Result<()> {
         while running.load(Ordering::Relaxed) {
-            if let Some((ref mut socket, _)) = self.socket_handler.socket {
-                // TODO: return error instead of panic?
-                let message = socket.next().await.unwrap()?;
-
-                match message {
-                    Message::Text(msg) => {
-                        if msg.is_empty() {
-                            return Ok(());
-                        }
-                        let event: WE = from_str(msg.as_str())?;
-                        (self.handler)(event)?;
-                    }
-                    Message::Ping(_) | Message::Pong(_) | Message::Binary(_) | Message::Frame(_) => {}
-                    Message::Close(e) => {
-                        return Err(Error::Msg(format!("Disconnected {e:?}")));
-                    }
+            let Some(message) = self
+                .socket_handler
+                .next_message(running, &mut self.connection_handler)
+                .await?
+            else {
+                break;
+            };
+            if let Message::Text(msg) = message {
+                if msg.is_empty() {
+                    return Ok(());
                 }
+                let event: WE = parse_event(&self.socket_handler.stream, msg.as_str())?;
+                (self.handler)(event)?;
             }
         }
         Ok(())
@@ -191,6 +779,7 @@ impl<'a, WE: serde::de::DeserializeOwned> WebSockets<'a, WE> {
 pub struct StringSocket<'a> {
     pub socket_handler: SocketHandler,
     handler: Box<dyn FnMut(&str) -> Result<()> + 'a + Send>,
+    connection_handler: Option<ConnectionHandler<'a>>,
 }
 
 impl<'a> StringSocket<'a> {
@@ -209,9 +798,20 @@ impl<'a> StringSocket<'a> {
         StringSocket {
             socket_handler,
             handler: Box::new(handler),
+            connection_handler: None,
         }
     }
 
+    /// Sets the handler notified when the websocket disconnects and reconnects, see
+    /// [`crate::config::Config::set_reconnect_policy`]
+    pub fn set_connection_handler<Callback>(mut self, handler: Callback) -> Self
+    where
+        Callback: FnMut(ConnectionEvent) -> Result<()> + 'a + Send,
+    {
+        self.connection_handler = Some(Box::new(handler));
+        self
+    }
+
     pub async fn connect_multiple(&mut self, endpoints: Vec<String>) -> Result<()> {
         self.socket_handler.connect_multiple(endpoints).await
     }
@@ -224,29 +824,242 @@ impl<'a> StringSocket<'a> {
 
     pub async fn handle_connect(&mut self, url: Url) -> Result<()> { self.socket_handler.handle_connect(url).await }
 
+    /// A sender to subscribe to streams or unsubscribe from them while the event loop runs
+    pub fn sender(&mut self) -> WebSocketSender { self.socket_handler.sender() }
+
     pub async fn disconnect(&mut self) -> Result<()> { self.socket_handler.disconnect().await }
 
     pub async fn event_loop(&mut self, running: &AtomicBool) -> Result<()> {
         while running.load(Ordering::Relaxed) {
-            if let Some((ref mut socket, _)) = self.socket_handler.socket {
-                // TODO: return error instead of panic?
-                let message = socket.next().await.unwrap()?;
-
-                match message {
-                    Message::Text(msg) => {
-                        if msg.is_empty() {
-                            return Ok(());
-                        }
-                        let event: &str = msg.as_str();
-                        (self.handler)(event)?;
-                    }
-                    Message::Ping(_) | Message::Pong(_) | Message::Binary(_) | Message::Frame(_) => {}
-                    Message::Close(e) => {
-                        return Err(Error::Msg(format!("Disconnected {e:?}")));
-                    }
+            let Some(message) = self
+                .socket_handler
+                .next_message(running, &mut self.connection_handler)
+                .await?
+            else {
+                break;
+            };
+            if let Message::Text(msg) = message {
+                if msg.is_empty() {
+                    return Ok(());
                 }
+                let event: &str = msg.as_str();
+                #[cfg(feature = "metrics")]
+                crate::metrics::record_ws_message(&self.socket_handler.stream, event);
+                (self.handler)(event)?;
             }
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+
+    #[test]
+    fn test_backoff() {
+        let policy = ReconnectPolicy {
+            max_attempts: None,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(8),
+        };
+        for (attempt, max) in [(0, 1), (1, 2), (3, 8), (10, 8), (u32::MAX, 8)] {
+            let backoff = policy.backoff(attempt);
+            let max = Duration::from_secs(max);
+            assert!(backoff >= max / 2 && backoff <= max, "{attempt}: {backoff:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reconnects_to_the_same_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut paths = vec![];
+            for i in 0..2 {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut path = String::new();
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                    path = request.uri().to_string();
+                    Ok(response)
+                })
+                .await
+                .unwrap();
+                paths.push(path);
+                socket.send(Message::Text(format!(r#"{{"i":{i}}}"#))).await.unwrap();
+                socket.close(None).await.unwrap();
+            }
+            paths
+        });
+
+        let config = Config::default()
+            .set_ws_endpoint(format!("ws://127.0.0.1:{port}"))
+            .set_reconnect_policy(ReconnectPolicy {
+                max_attempts: Some(3),
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(10),
+            });
+        let running = AtomicBool::new(true);
+        let mut messages = vec![];
+        let mut events = vec![];
+        let mut socket = StringSocket::new_with_options(
+            |msg: &str| {
+                messages.push(msg.to_string());
+                if messages.len() == 2 {
+                    running.store(false, Ordering::Relaxed);
+                }
+                Ok(())
+            },
+            config,
+        )
+        .set_connection_handler(|event| {
+            events.push(event);
+            Ok(())
+        });
+        socket.connect("btcusdt@trade").await.unwrap();
+        socket.event_loop(&running).await.unwrap();
+        drop(socket);
+
+        assert_eq!(messages, vec![r#"{"i":0}"#, r#"{"i":1}"#]);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], ConnectionEvent::Disconnected { .. }));
+        assert!(
+            matches!(events[1], ConnectionEvent::Reconnected { attempts: 1, gap } if gap.reconnected_at >= gap.disconnected_at)
+        );
+        assert_eq!(server.await.unwrap(), vec!["/ws/btcusdt@trade", "/ws/btcusdt@trade"]);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for i in 0..3 {
+                socket.send(Message::Text(format!(r#"{{"i":{i}}}"#))).await.unwrap();
+            }
+            // Echo text messages until the client closes the socket
+            while let Some(Ok(message)) = socket.next().await {
+                if message.is_text() {
+                    socket.send(message).await.unwrap();
+                }
+            }
+        });
+
+        let mut socket_handler =
+            SocketHandler::new_with_options(Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}")));
+        socket_handler.connect("btcusdt@trade").await.unwrap();
+        let (sender, events) = socket_handler.into_stream::<serde_json::Value>().split();
+        let mut events = std::pin::pin!(events.filter_map(|event| async move { event.ok()?.get("i")?.as_u64() }));
+        assert_eq!(events.by_ref().take(2).collect::<Vec<_>>().await, vec![0, 1]);
+
+        sender.send_text(r#"{"i":42}"#).await.unwrap();
+        assert_eq!(events.next().await, Some(2));
+        assert_eq!(events.next().await, Some(42));
+        sender.close().await.unwrap();
+        assert_eq!(events.next().await, None);
+    }
+
+    /// Reply to a subscription request like the server does
+    fn reply(request: &serde_json::Value) -> String {
+        let id = &request["id"];
+        match request["method"].as_str() {
+            Some("LIST_SUBSCRIPTIONS") => serde_json::json!({ "result": ["ethusdt@trade"], "id": id }),
+            _ if request["params"][0] == "invalid" => {
+                serde_json::json!({ "error": { "code": 2, "msg": "Invalid request" }, "id": id })
+            }
+            _ => serde_json::json!({ "result": null, "id": id }),
+        }
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_live_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut restored = vec![];
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(msg))) = socket.next().await {
+                let request: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                socket.send(Message::Text(reply(&request))).await.unwrap();
+                if request["method"] == "LIST_SUBSCRIPTIONS" {
+                    break;
+                }
+            }
+            socket.close(None).await.unwrap();
+
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for _ in 0..2 {
+                let Some(Ok(Message::Text(msg))) = socket.next().await else {
+                    panic!("expected a request");
+                };
+                let request: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                socket.send(Message::Text(reply(&request))).await.unwrap();
+                restored.push((request["method"].clone(), request["params"].clone()));
+            }
+            socket.send(Message::Text(r#"{"i":1}"#.to_string())).await.unwrap();
+            restored
+        });
+
+        let config = Config::default()
+            .set_ws_endpoint(format!("ws://127.0.0.1:{port}"))
+            .set_reconnect_policy(ReconnectPolicy {
+                max_attempts: Some(3),
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(10),
+            });
+        let running = AtomicBool::new(true);
+        let mut socket = StringSocket::new_with_options(
+            |_: &str| {
+                running.store(false, Ordering::Relaxed);
+                Ok(())
+            },
+            config,
+        );
+        socket
+            .connect_multiple(vec!["btcusdt@trade".to_string()])
+            .await
+            .unwrap();
+        let sender = socket.sender();
+        let requests = async {
+            sender.subscribe(vec!["ethusdt@trade".to_string()]).await.unwrap();
+            let error = sender.subscribe(vec!["invalid".to_string()]).await.unwrap_err();
+            assert!(matches!(error, Error::BinanceError { response } if response.code == 2));
+            sender.unsubscribe(vec!["btcusdt@trade".to_string()]).await.unwrap();
+            assert_eq!(sender.list_subscriptions().await.unwrap(), vec!["ethusdt@trade"]);
+        };
+        let (result, ()) = tokio::join!(socket.event_loop(&running), requests);
+        result.unwrap();
+
+        let subscriptions: Vec<&str> = socket.socket_handler.subscriptions().collect();
+        assert_eq!(subscriptions, vec!["ethusdt@trade"]);
+        assert_eq!(server.await.unwrap(), vec![
+            ("SUBSCRIBE".into(), serde_json::json!(["ethusdt@trade"])),
+            ("UNSUBSCRIBE".into(), serde_json::json!(["btcusdt@trade"])),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_disconnection_without_reconnect_policy() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            socket.close(None).await.unwrap();
+        });
+
+        let config = Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut socket = StringSocket::new_with_options(|_: &str| Ok(()), config);
+        socket.connect("btcusdt@trade").await.unwrap();
+        let error = socket.event_loop(&AtomicBool::new(true)).await.unwrap_err();
+        assert!(error.to_string().starts_with("Disconnected"), "{error}");
+    }
+}
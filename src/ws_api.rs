@@ -0,0 +1,415 @@
+//! Order entry over the websocket API, served by [`crate::config::Config::ws_api_endpoint`] for
+//! spot and [`crate::config::Config::futures_ws_api_endpoint`] for USD-M futures.
+//!
+//! Requests take the same structs as their REST endpoints. Their parameters are signed like the
+//! query string of a REST request, unless the connection was authenticated with
+//! [`WsApiClient::logon`]. Responses are matched to their request by id, so that several requests
+//! can be in flight on the same connection.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use url::Url;
+
+use crate::account::{CancelReplaceRequest, OrderCancellation, OrderRequest};
+use crate::client::handle_content_error;
+use crate::config::Config;
+use crate::credentials::{CredentialProvider, Credentials, SharedCredentials};
+use crate::errors::*;
+use crate::rest_model::{OrderCanceled, OrderCanceledReplaced, Transaction};
+use crate::signer::SignerCache;
+use crate::time_sync::TimeSync;
+use crate::util::{build_params_p, build_request};
+use crate::websockets::{EventStream, SocketHandler, WebSocketSender};
+
+/// The replies awaited by the requests in flight, `None` once the connection is closed
+type Pending = Arc<Mutex<Option<HashMap<String, oneshot::Sender<Result<Value>>>>>>;
+
+/// Status of the session returned by `session.logon`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    pub api_key: Option<String>,
+    pub authorized_since: Option<u64>,
+    pub connected_since: u64,
+    pub return_rate_limits: bool,
+    pub server_time: u64,
+}
+
+/// A connection to the websocket API
+///
+/// Credentials are those of the config's credential provider. The connection is not restored when
+/// lost: requests in flight and later ones fail, and a new client has to be connected.
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::account::OrderRequest;
+/// use binance::config::Config;
+/// use binance::credentials::Credentials;
+/// use binance::rest_model::{OrderSide, OrderType, TimeInForce};
+/// use binance::ws_api::WsApiClient;
+///
+/// # async fn run() -> binance::errors::Result<()> {
+/// let conf = Config::testnet().set_credential_provider(Credentials::new("api_key", "secret_key"));
+/// let client = WsApiClient::spot(&conf).await?;
+/// let order = OrderRequest {
+///     symbol: "BTCUSDT".to_string(),
+///     side: OrderSide::Buy,
+///     order_type: OrderType::Limit,
+///     time_in_force: Some(TimeInForce::GTC),
+///     quantity: Some(0.001),
+///     price: Some(20000.0),
+///     ..OrderRequest::default()
+/// };
+/// let transaction = client.place_order(order).await?;
+/// println!("{transaction:?}");
+/// # Ok(())
+/// # }
+/// ```
+pub struct WsApiClient {
+    sender: WebSocketSender,
+    pending: Pending,
+    reader: JoinHandle<()>,
+    credentials: Option<SharedCredentials>,
+    signer: SignerCache,
+    time_sync: TimeSync,
+    recv_window: u64,
+    next_id: AtomicU64,
+    logged_on: AtomicBool,
+}
+
+impl WsApiClient {
+    /// Connect to the spot websocket API
+    pub async fn spot(conf: &Config) -> Result<Self> { Self::connect(&conf.ws_api_endpoint, conf).await }
+
+    /// Connect to the USD-M futures websocket API
+    #[cfg(feature = "futures_api")]
+    pub async fn usd_m(conf: &Config) -> Result<Self> { Self::connect(&conf.futures_ws_api_endpoint, conf).await }
+
+    /// Connect to the websocket API at `endpoint`
+    pub async fn connect(endpoint: &str, conf: &Config) -> Result<Self> {
+        let mut conf = conf.clone();
+        conf.reconnect_policy = None;
+        let mut socket_handler = SocketHandler::new_with_options(conf.clone());
+        socket_handler.set_throttled(false);
+        socket_handler.handle_connect(Url::parse(endpoint)?).await?;
+        let (sender, events) = socket_handler.into_stream::<Value>().split();
+        let pending: Pending = Arc::new(Mutex::new(Some(HashMap::new())));
+        let reader = tokio::spawn(read_responses(events, pending.clone()));
+        Ok(WsApiClient {
+            sender,
+            pending,
+            reader,
+            credentials: conf.credentials,
+            signer: SignerCache::new(conf.key_type),
+            time_sync: conf.time_sync,
+            recv_window: conf.recv_window,
+            next_id: AtomicU64::new(1),
+            logged_on: AtomicBool::new(false),
+        })
+    }
+
+    /// Authenticate the connection with `session.logon`, later requests are sent without api key
+    /// nor signature. The exchange only accepts Ed25519 keys for it.
+    pub async fn logon(&self) -> Result<SessionStatus> {
+        let mut params = BTreeMap::new();
+        let credentials = self.credentials()?;
+        self.sign(&mut params, &credentials)?;
+        let status = self.send("session.logon", params).await?;
+        self.logged_on.store(true, Ordering::Relaxed);
+        Ok(serde_json::from_value(status)?)
+    }
+
+    /// Send a request of `method` with `params`, adding the timestamp and signature if `signed`
+    pub async fn request<P, T>(&self, method: &str, params: P, signed: bool) -> Result<T>
+    where
+        P: Serialize,
+        T: DeserializeOwned,
+    {
+        let mut params = build_params_p(params)?;
+        if signed {
+            params.insert("timestamp".to_string(), self.time_sync.timestamp().to_string());
+            if self.recv_window > 0 {
+                params
+                    .entry("recvWindow".to_string())
+                    .or_insert_with(|| self.recv_window.to_string());
+            }
+            if !self.logged_on.load(Ordering::Relaxed) {
+                let credentials = self.credentials()?;
+                self.sign(&mut params, &credentials)?;
+            }
+        }
+        let result = self.send(method, params).await?;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Place an order with `order.place`
+    pub async fn place_order(&self, order: OrderRequest) -> Result<Transaction> {
+        order.valid()?;
+        self.request("order.place", order, true).await
+    }
+
+    /// Cancel an order with `order.cancel`
+    pub async fn cancel_order(&self, cancellation: OrderCancellation) -> Result<OrderCanceled> {
+        self.request("order.cancel", cancellation, true).await
+    }
+
+    /// Cancel an order and place a new one on the same symbol with `order.cancelReplace`
+    pub async fn cancel_replace_order(&self, order: CancelReplaceRequest) -> Result<OrderCanceledReplaced> {
+        order.valid()?;
+        self.request("order.cancelReplace", order, true).await
+    }
+
+    /// Place a futures order with `order.place`, on a client connected with [`WsApiClient::usd_m`]
+    #[cfg(feature = "futures_api")]
+    pub async fn place_futures_order(
+        &self,
+        order: crate::futures::account::OrderRequest,
+    ) -> Result<crate::futures::rest_model::Transaction> {
+        self.request("order.place", order, true).await
+    }
+
+    /// Close the connection, failing the requests in flight
+    pub async fn close(&self) -> Result<()> { self.sender.close().await }
+
+    fn credentials(&self) -> Result<Credentials> {
+        self.credentials
+            .as_ref()
+            .ok_or_else(|| Error::CredentialsUnavailable("no credential provider configured".to_string()))?
+            .credentials()
+    }
+
+    /// Add the api key, the timestamp if missing, and the signature of the sorted parameters
+    fn sign(&self, params: &mut BTreeMap<String, String>, credentials: &Credentials) -> Result<()> {
+        params
+            .entry("timestamp".to_string())
+            .or_insert_with(|| self.time_sync.timestamp().to_string());
+        params.insert("apiKey".to_string(), credentials.api_key().to_string());
+        let signature = self
+            .signer
+            .signer(credentials)?
+            .signature(&build_request(params.iter()))?;
+        params.insert("signature".to_string(), signature);
+        Ok(())
+    }
+
+    async fn send(&self, method: &str, params: BTreeMap<String, String>) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let (reply, response) = oneshot::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(pending) => pending.insert(id.clone(), reply),
+            None => return Err(connection_closed()),
+        };
+        let request = serde_json::json!({ "id": id, "method": method, "params": params });
+        if let Err(e) = self.sender.send_text(request.to_string()).await {
+            if let Some(pending) = self.pending.lock().unwrap().as_mut() {
+                pending.remove(&id);
+            }
+            return Err(e);
+        }
+        response.await.unwrap_or_else(|_| Err(connection_closed()))
+    }
+}
+
+impl Drop for WsApiClient {
+    fn drop(&mut self) { self.reader.abort(); }
+}
+
+fn connection_closed() -> Error { Error::Msg("websocket API connection closed".to_string()) }
+
+/// Reply to the pending requests until the connection ends, then fail those left
+async fn read_responses(mut events: EventStream<Value>, pending: Pending) {
+    while let Some(response) = events.next().await {
+        let Ok(response) = response else {
+            continue;
+        };
+        let Some(id) = response.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        let reply = pending.lock().unwrap().as_mut().and_then(|pending| pending.remove(id));
+        if let Some(reply) = reply {
+            let _ = reply.send(response_result(response));
+        }
+    }
+    pending.lock().unwrap().take();
+}
+
+/// The `result` of a response, or its `error` mapped like the errors of REST responses
+fn response_result(mut response: Value) -> Result<Value> {
+    match response.get("status").and_then(Value::as_u64) {
+        Some(200) => Ok(response["result"].take()),
+        _ => {
+            let error: BinanceContentError = serde_json::from_value(response["error"].take())?;
+            Err(handle_content_error(error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rest_model::{OrderSide, OrderType, TimeInForce};
+    use crate::signer::Signer;
+    use futures::SinkExt;
+    use std::time::{Duration, Instant};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    async fn server() -> (String, TcpListener) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("ws://{}/ws-api/v3", listener.local_addr().unwrap());
+        (endpoint, listener)
+    }
+
+    async fn next_request(socket: &mut WebSocketStream<TcpStream>) -> Value {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => serde_json::from_str(&text).unwrap(),
+            message => panic!("unexpected message {message:?}"),
+        }
+    }
+
+    fn config() -> Config { Config::default().set_credential_provider(Credentials::new("key", "secret")) }
+
+    #[tokio::test]
+    async fn test_signs_and_correlates_requests() {
+        let (endpoint, listener) = server().await;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut requests = [next_request(&mut socket).await, next_request(&mut socket).await];
+            requests.sort_by_key(|request| request["method"].as_str().unwrap().to_string());
+            let [cancel, place] = requests;
+            assert_eq!(cancel["method"], "order.cancel");
+            assert_eq!(place["method"], "order.place");
+            let params = place["params"].as_object().unwrap();
+            assert_eq!(params["apiKey"], "key");
+            assert_eq!(params["price"], "20000");
+            assert_eq!(params["recvWindow"], "5000");
+            let payload = params
+                .iter()
+                .filter(|(k, _)| *k != "signature")
+                .map(|(k, v)| format!("{k}={}", v.as_str().unwrap()))
+                .collect::<Vec<_>>()
+                .join("&");
+            let signature = Signer::hmac("secret").signature(&payload).unwrap();
+            assert_eq!(params["signature"], signature.as_str());
+            // Answer in the reverse order of the requests
+            let cancel_response = serde_json::json!({
+                "id": cancel["id"],
+                "status": 400,
+                "error": { "code": -2011, "msg": "Unknown order sent." }
+            });
+            let place_response = serde_json::json!({
+                "id": place["id"],
+                "status": 200,
+                "result": {
+                    "symbol": "BTCUSDT", "orderId": 12569099453u64, "clientOrderId": "4d96324ff9d44481926157",
+                    "transactTime": 1660801715639u64, "price": "20000.00000000", "origQty": "0.00100000",
+                    "executedQty": "0.00000000", "cummulativeQuoteQty": "0.00000000", "status": "NEW",
+                    "timeInForce": "GTC", "type": "LIMIT", "side": "BUY", "fills": []
+                }
+            });
+            for response in [cancel_response, place_response] {
+                socket.send(Message::Text(response.to_string())).await.unwrap();
+            }
+            socket.next().await;
+        });
+
+        let client = WsApiClient::connect(&endpoint, &config()).await.unwrap();
+        let order = OrderRequest {
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: Some(TimeInForce::GTC),
+            quantity: Some(0.001),
+            price: Some(20000.0),
+            ..OrderRequest::default()
+        };
+        let cancellation = OrderCancellation {
+            symbol: "BTCUSDT".to_string(),
+            order_id: Some(1),
+            ..OrderCancellation::default()
+        };
+        let (transaction, canceled) = tokio::join!(client.place_order(order), client.cancel_order(cancellation));
+        assert_eq!(transaction.unwrap().order_id, 12569099453);
+        assert!(matches!(canceled, Err(Error::UnknownOrder { .. })));
+        client.close().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_logon_drops_signature() {
+        let (endpoint, listener) = server().await;
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let logon = next_request(&mut socket).await;
+            assert_eq!(logon["method"], "session.logon");
+            assert_eq!(logon["params"]["apiKey"], "key");
+            assert!(logon["params"]["signature"].is_string());
+            let response = serde_json::json!({
+                "id": logon["id"],
+                "status": 200,
+                "result": {
+                    "apiKey": "key", "authorizedSince": 1649729878532u64, "connectedSince": 1649729873021u64,
+                    "returnRateLimits": false, "serverTime": 1649729878630u64
+                }
+            });
+            socket.send(Message::Text(response.to_string())).await.unwrap();
+            let cancel = next_request(&mut socket).await;
+            let params = cancel["params"].as_object().unwrap();
+            assert!(params.contains_key("timestamp"));
+            assert!(!params.contains_key("apiKey") && !params.contains_key("signature"));
+            // Close without answering, failing the request
+        });
+
+        let client = WsApiClient::connect(&endpoint, &config()).await.unwrap();
+        let status = client.logon().await.unwrap();
+        assert_eq!(status.api_key.as_deref(), Some("key"));
+        let cancellation = OrderCancellation {
+            symbol: "BTCUSDT".to_string(),
+            order_id: Some(1),
+            ..OrderCancellation::default()
+        };
+        assert!(client.cancel_order(cancellation).await.is_err());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_not_throttled() {
+        let (endpoint, listener) = server().await;
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(Message::Text(text))) = socket.next().await {
+                let request: Value = serde_json::from_str(&text).unwrap();
+                let response = serde_json::json!({ "id": request["id"], "status": 200, "result": {} });
+                socket.send(Message::Text(response.to_string())).await.unwrap();
+            }
+        });
+
+        let client = WsApiClient::connect(&endpoint, &config()).await.unwrap();
+        let started = Instant::now();
+        for _ in 0..20 {
+            let _: Value = client
+                .request("ping", BTreeMap::<String, String>::new(), false)
+                .await
+                .unwrap();
+        }
+        // Throttled as the market streams, the last 15 requests would wait for 3 seconds
+        assert!(
+            started.elapsed() < Duration::from_millis(500),
+            "{:?}",
+            started.elapsed()
+        );
+    }
+}
@@ -36,12 +36,23 @@ pub enum WebsocketEvent {
     ListOrderUpdate(Box<OrderListUpdate>),
     #[serde(alias = "markPriceUpdate")]
     MarkPriceUpdate(Box<MarkPriceEvent>),
+    #[serde(alias = "listenKeyExpired")]
+    ListenKeyExpired(Box<ListenKeyExpiredEvent>),
 }
 
+/// Reply to a request sent on a websocket, such as `SUBSCRIBE`
 #[derive(Serialize, Deserialize, Debug)]
 pub struct QueryResult {
-    pub result: Option<String>,
+    pub result: Option<serde_json::Value>,
     pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<QueryError>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QueryError {
+    pub code: i32,
+    pub msg: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -390,6 +401,15 @@ pub struct EventBalance {
     pub locked: f64,
 }
 
+/// The listen key expired, the user data stream has to be started again
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ListenKeyExpiredEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "listenKey")]
+    pub listen_key: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BalanceUpdate {
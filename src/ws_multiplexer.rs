@@ -0,0 +1,480 @@
+//! Spreads subscriptions to many streams over as many websocket connections as needed.
+//!
+//! Binance accepts at most 1024 streams per connection, and long combined stream urls are
+//! rejected well before that. A [`StreamMultiplexer`] opens a connection per
+//! [`StreamMultiplexer::set_streams_per_connection`] streams and merges their events into one
+//! [`Stream`].
+
+use std::collections::BTreeSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::errors::*;
+use crate::websockets::{SocketHandler, WebSocketSender};
+
+/// Streams per connection by default, which keeps combined stream urls short
+pub static DEFAULT_STREAMS_PER_CONNECTION: usize = 200;
+/// Most streams Binance accepts on a single connection
+pub static MAX_STREAMS_PER_CONNECTION: usize = 1024;
+/// Events buffered from all connections before they stop reading their socket
+static EVENTS_CAPACITY: usize = 1024;
+
+/// Websocket endpoints a multiplexer connects to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamEndpoint {
+    #[default]
+    Spot,
+    UsdMFutures,
+    CoinMFutures,
+}
+
+struct Shard {
+    streams: BTreeSet<String>,
+    sender: WebSocketSender,
+    task: JoinHandle<()>,
+}
+
+/// Merges the combined stream events of several connections, `WE` is usually a
+/// [`crate::ws_model::CombinedStreamEvent`].
+///
+/// New streams first fill the connections with room left, using `SUBSCRIBE` requests, then open
+/// new connections. Connections left without streams are closed, and after unsubscribing the
+/// streams of the emptiest connection move to the others when they have room for them.
+/// Subscriptions are only recorded once the server confirms them, and failed requests are returned
+/// by [`StreamMultiplexer::subscribe`] and [`StreamMultiplexer::unsubscribe`]. The replies are read
+/// along with the events, so the multiplexer should be polled meanwhile when it holds more than
+/// 1024 unread events.
+///
+/// Connections whose stream ended, such as disconnected sockets without
+/// [`crate::config::Config::set_reconnect_policy`], are dropped along with their streams, which
+/// can then be subscribed to again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use binance::config::Config;
+/// use binance::websockets::agg_trade_stream;
+/// use binance::ws_model::{CombinedStreamEvent, WebsocketEvent};
+/// use binance::ws_multiplexer::{StreamEndpoint, StreamMultiplexer};
+/// use futures::StreamExt;
+///
+/// # async fn run(symbols: Vec<String>) -> binance::errors::Result<()> {
+/// let mut multiplexer: StreamMultiplexer<CombinedStreamEvent<WebsocketEvent>> =
+///     StreamMultiplexer::new(Config::default(), StreamEndpoint::Spot);
+/// multiplexer
+///     .subscribe(symbols.iter().map(|symbol| agg_trade_stream(symbol)).collect())
+///     .await?;
+/// while let Some(event) = multiplexer.next().await {
+///     println!("{:?}", event?.data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct StreamMultiplexer<WE> {
+    conf: Config,
+    endpoint: StreamEndpoint,
+    streams_per_connection: usize,
+    shards: Vec<Shard>,
+    events_tx: mpsc::Sender<Result<WE>>,
+    events_rx: mpsc::Receiver<Result<WE>>,
+}
+
+impl<WE> StreamMultiplexer<WE>
+where
+    WE: serde::de::DeserializeOwned + Send + 'static,
+{
+    pub fn new(conf: Config, endpoint: StreamEndpoint) -> Self {
+        let (events_tx, events_rx) = mpsc::channel(EVENTS_CAPACITY);
+        StreamMultiplexer {
+            conf,
+            endpoint,
+            streams_per_connection: DEFAULT_STREAMS_PER_CONNECTION,
+            shards: vec![],
+            events_tx,
+            events_rx,
+        }
+    }
+
+    /// Sets the most streams of a connection, capped at [`MAX_STREAMS_PER_CONNECTION`]. Only
+    /// applies to the streams subscribed to afterwards.
+    pub fn set_streams_per_connection(mut self, streams_per_connection: usize) -> Self {
+        self.streams_per_connection = streams_per_connection.clamp(1, MAX_STREAMS_PER_CONNECTION);
+        self
+    }
+
+    /// Every subscribed stream of the open connections
+    pub fn streams(&self) -> impl Iterator<Item = &str> {
+        self.shards
+            .iter()
+            .filter(|shard| !shard.task.is_finished())
+            .flat_map(|shard| shard.streams.iter().map(String::as_str))
+    }
+
+    /// The number of open connections
+    pub fn connections(&self) -> usize { self.shards.iter().filter(|shard| !shard.task.is_finished()).count() }
+
+    /// Subscribe to `streams`, ignoring those already subscribed to
+    pub async fn subscribe(&mut self, streams: Vec<String>) -> Result<()> {
+        self.remove_ended_shards();
+        let subscribed: BTreeSet<&str> = self.streams().collect();
+        let mut streams: Vec<String> = streams
+            .into_iter()
+            .filter(|stream| !subscribed.contains(stream.as_str()))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+
+        for shard in &mut self.shards {
+            let room = self.streams_per_connection.saturating_sub(shard.streams.len());
+            if room == 0 || streams.is_empty() {
+                continue;
+            }
+            let added: Vec<String> = streams.drain(..room.min(streams.len())).collect();
+            shard.sender.subscribe(added.clone()).await?;
+            shard.streams.extend(added);
+        }
+        while !streams.is_empty() {
+            let added: Vec<String> = streams
+                .drain(..self.streams_per_connection.min(streams.len()))
+                .collect();
+            let shard = self.connect(added).await?;
+            self.shards.push(shard);
+        }
+        Ok(())
+    }
+
+    /// Unsubscribe from `streams`, closing the connections left without streams and moving the
+    /// streams of the emptiest connections to the others while they have room for them
+    pub async fn unsubscribe(&mut self, streams: Vec<String>) -> Result<()> {
+        self.remove_ended_shards();
+        let streams: BTreeSet<String> = streams.into_iter().collect();
+        let mut shards = Vec::with_capacity(self.shards.len());
+        let mut result = Ok(());
+        for mut shard in std::mem::take(&mut self.shards) {
+            let removed: Vec<String> = shard.streams.intersection(&streams).cloned().collect();
+            if removed.is_empty() {
+                shards.push(shard);
+            } else if removed.len() == shard.streams.len() {
+                close(shard).await;
+            } else {
+                match shard.sender.unsubscribe(removed.clone()).await {
+                    Ok(()) => removed.iter().for_each(|stream| {
+                        shard.streams.remove(stream);
+                    }),
+                    Err(e) => result = Err(e),
+                }
+                shards.push(shard);
+            }
+        }
+        self.shards = shards;
+        result?;
+        self.compact().await
+    }
+
+    /// Moves the streams of the connection with the fewest streams to the others, then closes it,
+    /// as long as the others have room for them. The connection is only closed once the others
+    /// confirmed their subscriptions, so events of the moved streams may be received twice while
+    /// both connections are subscribed to them. When a subscription fails, the connection is kept
+    /// with the streams which could not move.
+    async fn compact(&mut self) -> Result<()> {
+        while self.shards.len() > 1 {
+            let Some(emptiest) = (0..self.shards.len()).min_by_key(|&i| self.shards[i].streams.len()) else {
+                return Ok(());
+            };
+            let room: usize = self
+                .shards
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != emptiest)
+                .map(|(_, shard)| self.streams_per_connection.saturating_sub(shard.streams.len()))
+                .sum();
+            if self.shards[emptiest].streams.len() > room {
+                return Ok(());
+            }
+            let mut shard = self.shards.remove(emptiest);
+            let mut streams: Vec<String> = shard.streams.iter().cloned().collect();
+            let mut moved_streams = vec![];
+            let mut result = Ok(());
+            for target in &mut self.shards {
+                let room = self.streams_per_connection.saturating_sub(target.streams.len());
+                if room == 0 || streams.is_empty() {
+                    continue;
+                }
+                let moved: Vec<String> = streams.drain(..room.min(streams.len())).collect();
+                if let Err(e) = target.sender.subscribe(moved.clone()).await {
+                    result = Err(e);
+                    break;
+                }
+                target.streams.extend(moved.iter().cloned());
+                moved_streams.extend(moved);
+            }
+            if let Err(e) = result {
+                moved_streams.iter().for_each(|stream| {
+                    shard.streams.remove(stream);
+                });
+                // Events of the streams which moved are received twice should this fail
+                if !moved_streams.is_empty() {
+                    let _ = shard.sender.unsubscribe(moved_streams).await;
+                }
+                self.shards.push(shard);
+                return Err(e);
+            }
+            close(shard).await;
+        }
+        Ok(())
+    }
+
+    /// Drops the connections whose stream ended
+    fn remove_ended_shards(&mut self) { self.shards.retain(|shard| !shard.task.is_finished()); }
+
+    async fn connect(&self, streams: Vec<String>) -> Result<Shard> {
+        let mut socket_handler = SocketHandler::new_with_options(self.conf.clone());
+        match self.endpoint {
+            StreamEndpoint::Spot => socket_handler.connect_multiple(streams.clone()).await?,
+            StreamEndpoint::UsdMFutures => socket_handler.connect_multiple_futures(streams.clone()).await?,
+            StreamEndpoint::CoinMFutures => socket_handler.connect_multiple_coin_m(streams.clone()).await?,
+        }
+        let (sender, mut events) = socket_handler.into_stream::<WE>().split();
+        let events_tx = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                if events_tx.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Shard {
+            streams: streams.into_iter().collect(),
+            sender,
+            task,
+        })
+    }
+}
+
+/// Close the socket of `shard`, whose task ends once the socket is closed. The socket of a task
+/// which already ended is closed as well.
+async fn close(shard: Shard) {
+    if shard.sender.close().await.is_err() {
+        shard.task.abort();
+    }
+}
+
+impl<WE> Stream for StreamMultiplexer<WE> {
+    type Item = Result<WE>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events_rx.poll_recv(cx)
+    }
+}
+
+impl<WE> Drop for StreamMultiplexer<WE> {
+    fn drop(&mut self) { self.shards.iter().for_each(|shard| shard.task.abort()); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::SinkExt;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::Request;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Accepts connections, sending an event named after the first stream of each, and replying
+    /// to subscription requests with an event for each new stream. Subscriptions are replied to
+    /// after `subscribe_delay`, and logged along with the closed connections.
+    async fn serve(listener: TcpListener, log: Arc<Mutex<Vec<String>>>, subscribe_delay: Duration) {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let log = log.clone();
+            tokio::spawn(async move {
+                let mut query = String::new();
+                let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+                    query = request.uri().query().unwrap_or_default().to_string();
+                    Ok(response)
+                })
+                .await
+                .unwrap();
+                let first = query
+                    .trim_start_matches("streams=")
+                    .split('/')
+                    .next()
+                    .unwrap()
+                    .to_string();
+                let event = serde_json::json!({ "stream": first, "data": {} }).to_string();
+                socket.send(Message::Text(event)).await.unwrap();
+                if first == "closed@trade" {
+                    socket.close(None).await.unwrap();
+                    return;
+                }
+                while let Some(Ok(Message::Text(msg))) = socket.next().await {
+                    let request: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                    if request["method"] == "SUBSCRIBE" {
+                        tokio::time::sleep(subscribe_delay).await;
+                        log.lock().unwrap().push(format!("subscribed {}", request["params"]));
+                    }
+                    if request["params"][0] == "rejected@trade" {
+                        let reply = serde_json::json!({
+                            "error": { "code": 2, "msg": "Invalid request: unknown stream" },
+                            "id": request["id"]
+                        });
+                        socket.send(Message::Text(reply.to_string())).await.unwrap();
+                        continue;
+                    }
+                    let reply = serde_json::json!({ "result": null, "id": request["id"] });
+                    socket.send(Message::Text(reply.to_string())).await.unwrap();
+                    if request["method"] == "SUBSCRIBE" {
+                        for stream in request["params"].as_array().unwrap() {
+                            let event = serde_json::json!({ "stream": stream, "data": {} }).to_string();
+                            socket.send(Message::Text(event)).await.unwrap();
+                        }
+                    }
+                }
+                log.lock().unwrap().push(format!("closed {first}"));
+            });
+        }
+    }
+
+    async fn next_stream(multiplexer: &mut StreamMultiplexer<serde_json::Value>) -> String {
+        let event = tokio::time::timeout(Duration::from_secs(5), multiplexer.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        event["stream"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_spreads_streams_over_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, Arc::default(), Duration::ZERO));
+
+        let config = Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut multiplexer =
+            StreamMultiplexer::<serde_json::Value>::new(config, StreamEndpoint::Spot).set_streams_per_connection(2);
+        let streams = ["a@trade", "b@trade", "c@trade"].map(String::from).to_vec();
+        multiplexer.subscribe(streams.clone()).await.unwrap();
+        multiplexer.subscribe(streams).await.unwrap();
+        assert_eq!(multiplexer.connections(), 2);
+        let mut first = vec![next_stream(&mut multiplexer).await, next_stream(&mut multiplexer).await];
+        first.sort();
+        assert_eq!(first, vec!["a@trade", "c@trade"]);
+
+        // Fills the second connection before opening a third one
+        multiplexer
+            .subscribe(vec!["d@trade".to_string(), "e@trade".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(multiplexer.connections(), 3);
+        let mut added = vec![next_stream(&mut multiplexer).await, next_stream(&mut multiplexer).await];
+        added.sort();
+        assert_eq!(added, vec!["d@trade", "e@trade"]);
+
+        multiplexer
+            .unsubscribe(vec![
+                "a@trade".to_string(),
+                "b@trade".to_string(),
+                "c@trade".to_string(),
+            ])
+            .await
+            .unwrap();
+        // The remaining streams of two connections fit in one
+        assert_eq!(multiplexer.connections(), 1);
+        assert_eq!(multiplexer.streams().collect::<Vec<_>>(), vec!["d@trade", "e@trade"]);
+    }
+
+    #[tokio::test]
+    async fn test_drops_ended_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, Arc::default(), Duration::ZERO));
+
+        let config = Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut multiplexer =
+            StreamMultiplexer::<serde_json::Value>::new(config, StreamEndpoint::Spot).set_streams_per_connection(1);
+        multiplexer
+            .subscribe(vec!["a@trade".to_string(), "closed@trade".to_string()])
+            .await
+            .unwrap();
+        // The ended connection reports its disconnection
+        let mut events = vec![];
+        for _ in 0..3 {
+            let event = tokio::time::timeout(Duration::from_secs(5), multiplexer.next())
+                .await
+                .unwrap()
+                .unwrap();
+            events.push(event.map_or_else(|_| "error".to_string(), |event| event["stream"].to_string()));
+        }
+        events.sort();
+        assert_eq!(events, vec!["\"a@trade\"", "\"closed@trade\"", "error"]);
+        for _ in 0..100 {
+            if multiplexer.connections() == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(multiplexer.connections(), 1);
+        assert_eq!(multiplexer.streams().collect::<Vec<_>>(), vec!["a@trade"]);
+
+        // Unsubscribing from the stream of the ended connection does nothing
+        multiplexer.unsubscribe(vec!["closed@trade".to_string()]).await.unwrap();
+        assert_eq!(multiplexer.connections(), 1);
+        multiplexer.subscribe(vec!["closed@trade".to_string()]).await.unwrap();
+        assert_eq!(next_stream(&mut multiplexer).await, "closed@trade");
+    }
+
+    #[tokio::test]
+    async fn test_compacts_after_subscribing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let log = Arc::new(Mutex::new(vec![]));
+        tokio::spawn(serve(listener, log.clone(), Duration::from_millis(300)));
+
+        let config = Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut multiplexer =
+            StreamMultiplexer::<serde_json::Value>::new(config, StreamEndpoint::Spot).set_streams_per_connection(2);
+        multiplexer
+            .subscribe(["a@trade", "b@trade", "c@trade"].map(String::from).to_vec())
+            .await
+            .unwrap();
+        multiplexer.unsubscribe(vec!["a@trade".to_string()]).await.unwrap();
+        assert_eq!(multiplexer.connections(), 1);
+        assert_eq!(multiplexer.streams().collect::<Vec<_>>(), vec!["b@trade", "c@trade"]);
+        for _ in 0..100 {
+            if log.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        // The first connection is closed once its stream is subscribed to on the second one
+        assert_eq!(*log.lock().unwrap(), vec![
+            r#"subscribed ["b@trade"]"#,
+            "closed a@trade"
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_returns_failed_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, Arc::default(), Duration::ZERO));
+
+        let config = Config::default().set_ws_endpoint(format!("ws://127.0.0.1:{port}"));
+        let mut multiplexer =
+            StreamMultiplexer::<serde_json::Value>::new(config, StreamEndpoint::Spot).set_streams_per_connection(3);
+        multiplexer.subscribe(vec!["a@trade".to_string()]).await.unwrap();
+        for _ in 0..2 {
+            let subscribed = multiplexer.subscribe(vec!["rejected@trade".to_string()]).await;
+            assert!(matches!(subscribed, Err(Error::BinanceError { .. })), "{subscribed:?}");
+            assert_eq!(multiplexer.streams().collect::<Vec<_>>(), vec!["a@trade"]);
+        }
+        assert_eq!(multiplexer.connections(), 1);
+    }
+}